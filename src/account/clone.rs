@@ -3,10 +3,12 @@ use dialoguer::Input;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
-use crate::{ common::network, context::Valid8Context };
+use valid8::Valid8Context;
+
+use crate::prompt;
 
 pub fn clone(ctx: &mut Valid8Context) -> Result<()> {
-    let network = network::get(ctx)?;
+    let network = prompt::network(ctx)?;
     
     let mut address: Option<Pubkey> = None;
     while address.is_none() {
@@ -21,6 +23,8 @@ pub fn clone(ctx: &mut Valid8Context) -> Result<()> {
     }
 
     let pubkey = address.ok_or(anyhow!("Public key not defined"))?;
-    
+    if ctx.has_account(&pubkey) {
+        println!("{} already added", pubkey);
+    }
     ctx.add_account(&network, &pubkey)
 }
\ No newline at end of file
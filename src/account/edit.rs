@@ -1,25 +1,15 @@
 use anyhow::{anyhow, Result};
 use dialoguer::{Input, Select};
 use solana_sdk::pubkey::Pubkey;
-use std::str::FromStr;
 
-use crate::context::{EditField, Valid8Context};
+use valid8::{EditField, Valid8Context};
 
-pub fn edit(ctx: &mut Valid8Context) -> Result<()> {
-
-    let mut address: Option<Pubkey> = None;
-    while address.is_none() {
-        let address_string: String = Input::new()
-            .with_prompt("Account address to edit")
-            .interact_text()?;
+use crate::prompt;
 
-        match Pubkey::from_str(&address_string) {
-            Ok(p) => address = Some(p),
-            Err(_) => println!("Invalid address: {}. Please enter a valid base58-encoeded Solana address.", &address_string)
-        }
-    }
+pub fn edit(ctx: &mut Valid8Context) -> Result<()> {
 
-    let pubkey = address.ok_or(anyhow!("Public key not defined"))?;
+    let pubkeys = ctx.accounts.iter().map(|acc| acc.pubkey).collect::<Vec<Pubkey>>();
+    let pubkey = prompt::select_pubkey(ctx, "Account to edit", &pubkeys)?;
     if ctx.has_account(&pubkey) {
         let account = ctx.accounts
             .iter()
@@ -28,7 +18,7 @@ pub fn edit(ctx: &mut Valid8Context) -> Result<()> {
         // let account = ctx.accounts.get(position).ok_or(anyhow!("No account at that position"))?;
 
         let fields: Vec<String> = vec![
-            format!("Owner: {}", account.owner.to_string()),
+            format!("Owner: {}", ctx.display_name(&account.owner)),
             format!("Lamports: {}", account.lamports.to_string()),
             format!("Unpack TokenAccount"),
             format!("Unpack PDA"),
@@ -49,11 +39,11 @@ pub fn edit(ctx: &mut Valid8Context) -> Result<()> {
                 ctx.edit_account(&pubkey, EditField::Lamports(new_lamports))?;
             },
             2 => {
-                ctx.edit_account(&pubkey, EditField::UnpackTokenAccount)?;
+                super::edit_token_account(ctx, &pubkey)?;
             },
             3 => {
-                todo!();
-                // ctx.edit_account(&pubkey, EditField::UnpackPDA(new_lamports))?;
+                let owner = account.owner;
+                super::edit_idl_account(ctx, &owner, &pubkey)?;
             },
             _ => {}
         }
@@ -0,0 +1,38 @@
+use anyhow::Result;
+use dialoguer::{Input, Select};
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::{common::idl, Valid8Context};
+
+pub fn edit_idl_account(ctx: &mut Valid8Context, program_id: &Pubkey, pubkey: &Pubkey) -> Result<()> {
+    // find the account type from its discriminator, or let the user select it from the IDL
+    let account_type = match ctx.find_idl_account_type(program_id, pubkey)? {
+        Some(account_type) => account_type,
+        None => {
+            let names = idl::open_idl(&ctx.project_name, program_id)?.accounts.into_iter().map(|a| a.name).collect::<Vec<String>>();
+            let selection = Select::new()
+                .with_prompt("Select the account type")
+                .items(&names)
+                .interact()?;
+            names[selection].clone()
+        }
+    };
+
+    let unpacked = ctx.unpack_idl_account(program_id, pubkey, &account_type)?;
+
+    // create a vector from the deserialized values for the user to select from
+    let account_fields = unpacked.fields.iter().map(|field| {
+        format!("{}: {:?}", field.name, field.value)
+    }).collect::<Vec<String>>();
+
+    let selection = Select::new()
+        .with_prompt(format!("Select {} field to edit.", account_type))
+        .items(&account_fields)
+        .interact_opt()?;
+
+    if let Some(index_select) = selection {
+        let new_value: String = Input::new().with_prompt("New value").interact_text()?;
+        ctx.edit_idl_account(program_id, pubkey, &account_type, &unpacked.fields[index_select].name, new_value)?;
+    }
+    Ok(())
+}
@@ -2,4 +2,10 @@ pub mod clone;
 pub use clone::*;
 
 pub mod edit;
-pub use edit::*;
\ No newline at end of file
+pub use edit::*;
+
+pub mod token;
+pub use token::*;
+
+pub mod idl;
+pub use idl::*;
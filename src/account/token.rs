@@ -0,0 +1,34 @@
+use anyhow::{anyhow, Result};
+use dialoguer::{Input, Select};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+
+use valid8::{EditField, Valid8Context};
+
+pub fn edit_token_account(ctx: &mut Valid8Context, pubkey: &Pubkey) -> Result<()> {
+    let account = ctx.accounts
+        .iter()
+        .find(|acc| acc.pubkey == *pubkey)
+        .ok_or(anyhow!("No account found in context"))?;
+
+    // deserialize token account data to Account struct to show the current values
+    let token_account = TokenAccount::unpack(&account.data)?;
+
+    let fields: Vec<String> = vec![
+        format!("Owner: {}", token_account.owner),
+        format!("Token amount: {}", token_account.amount),
+        format!("Token delegate: {:?}", token_account.delegate),
+        format!("Token delegate amount: {}", token_account.delegated_amount),
+    ];
+
+    let selection = Select::new().with_prompt("Select a field to edit").items(&fields).interact()?;
+
+    let edit_field = match selection {
+        0 => EditField::TokenOwner(Input::new().with_prompt("New owner pubkey:").interact_text()?),
+        1 => EditField::TokenAmount(Input::new().with_prompt("New amount:").interact_text()?),
+        2 => EditField::TokenDelegate(Input::new().with_prompt("New delegate pubkey:").interact_text()?),
+        3 => EditField::TokenDelegatedAmount(Input::new().with_prompt("New delegate amount:").interact_text()?),
+        _ => return Err(anyhow!("Invalid token account edit option"))
+    };
+    ctx.edit_account(pubkey, edit_field)
+}
@@ -0,0 +1,18 @@
+use std::path::Path;
+use anyhow::Result;
+
+use valid8::{common::bundle, Valid8Context};
+
+pub fn pack(ctx: &Valid8Context, out: &str) -> Result<()> {
+    // Save the config first, so the bundle matches the resources on disc
+    ctx.try_save_config()?;
+    let manifest = bundle::pack(&ctx.project_name, Path::new(out))?;
+    println!("✅ {} files packed to {}", manifest.files.len(), out);
+    Ok(())
+}
+
+pub fn unpack(path: &str, force: bool) -> Result<()> {
+    let manifest = bundle::unpack(Path::new(path), force)?;
+    println!("✅ {} files of {} unpacked, bundle version {} from valid8 {}", manifest.files.len(), manifest.project_name.to_config(), manifest.version, manifest.valid8_version);
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use valid8::common::storage;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CacheCommand {
+    /// Remove cached account data not used by any project
+    Gc {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+pub fn cache(command: &CacheCommand) -> Result<()> {
+    match command {
+        CacheCommand::Gc{dry_run} => {
+            let (removed, removed_bytes) = storage::gc(*dry_run)?;
+            let action = if *dry_run { "would be removed" } else { "removed" };
+            println!("✅ {} unused blobs ({} bytes) {} from {}", removed, removed_bytes, action, storage::cache_dir()?.display());
+        }
+    }
+    Ok(())
+}
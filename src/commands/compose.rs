@@ -1,9 +1,22 @@
 use anyhow::Result;
-use crate::context::Valid8Context;
+use valid8::{common::compose::MergePolicy, Valid8Context};
 
-pub fn compose(ctx: Valid8Context) -> Result<()> {
-    let (compose_count, account_count, program_count) = ctx.try_compose()?;
+pub fn compose(ctx: Valid8Context, policy: Option<MergePolicy>, update: bool, tags: &[String]) -> Result<()> {
+    let kept = match policy.or(ctx.compose_policy).unwrap_or_default() {
+        MergePolicy::Include => "included",
+        _ => "base",
+    };
+    let report = ctx.try_compose(policy, update, tags)?;
 
-    println!("✅ Valid8 configs composed! {} accounts and {} programs added from {} config(s)", account_count, program_count, compose_count);
+    if !report.conflicts.is_empty() {
+        println!("⚠️  {} conflicts, kept the {} definitions:", report.conflicts.len(), kept);
+        for conflict in report.conflicts.iter() {
+            println!("    {}", conflict);
+        }
+    }
+    println!("✅ Valid8 configs composed! {} accounts and {} programs added from {} config(s)", report.accounts, report.programs, report.configs);
+    if report.copied > 0 {
+        println!("{} accounts and IDLs copied from included fixtures", report.copied);
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,41 @@
+use std::fs;
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+
+use valid8::{config::CONFIG_VERSION, ConfigJson, ProjectName};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Check the config for schema errors and invalid references
+    Validate,
+}
+
+pub fn config(command: &ConfigCommand, offline: bool) -> Result<()> {
+    match command {
+        ConfigCommand::Validate => validate(&ProjectName::default(), offline),
+    }
+}
+
+fn validate(project_name: &ProjectName, offline: bool) -> Result<()> {
+    let path = project_name.to_config();
+    let buf = fs::read(&path).map_err(|e| anyhow!("Can't read {}: {}, run `valid8 init` to create it", path, e))?;
+    let (config, version) = ConfigJson::parse(&buf).map_err(|e| anyhow!("❌ {}: {}", path, e))?;
+    if version < CONFIG_VERSION {
+        println!("{} is config version {}, it's migrated to version {} on the next save", path, version, CONFIG_VERSION);
+    }
+
+    let problems = config.validate(offline);
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            println!("❌ {}", problem);
+        }
+        return Err(anyhow!("{} problems found in {}", problems.len(), path))
+    }
+
+    let missing = config.missing_accounts().len();
+    if missing > 0 {
+        println!("{} accounts are not installed in {}", missing, project_name.to_resources());
+    }
+    println!("✅ {} is valid, {} programs and {} accounts", path, config.programs.len(), config.accounts.len());
+    Ok(())
+}
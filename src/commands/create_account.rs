@@ -0,0 +1,61 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path, str::FromStr};
+use anyhow::{anyhow, Result};
+use clap::Args;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::{common::{idl, pda}, CreatedAccount, Valid8Context};
+
+use crate::commands::parse_seed;
+
+#[derive(Debug, Clone, Args)]
+pub struct CreateAccountArgs {
+    /// Address of the new account, or use --pda
+    pub pubkey: Option<String>,
+    /// Derive the address from the seeds of this IDL account
+    #[arg(long = "pda")]
+    pub pda_account: Option<String>,
+    /// Seed value as <path>=<value>, for --pda
+    #[arg(long = "seed", value_parser = parse_seed)]
+    pub seeds: Vec<(String, String)>,
+    /// Program owning the account, its id or label
+    #[arg(long = "program")]
+    pub program_id: String,
+    /// IDL account type
+    #[arg(long = "type")]
+    pub account_type: String,
+    /// Json file with the account values
+    #[arg(long)]
+    pub json: String,
+    /// Account size, if larger than the serialized values
+    #[arg(long)]
+    pub space: Option<usize>,
+}
+
+pub fn create_account(ctx: &mut Valid8Context, args: &CreateAccountArgs) -> Result<()> {
+    let program_id = &ctx.resolve(&args.program_id)?;
+    let pubkey = match (&args.pubkey, &args.pda_account) {
+        (Some(pubkey), None) => Pubkey::from_str(pubkey)?,
+        (None, Some(account_name)) => {
+            let idl = idl::open_idl(&ctx.project_name, program_id)?;
+            let seeds: HashMap<String, String> = args.seeds.iter().cloned().collect();
+            pda::derive_pda(ctx, &idl, program_id, account_name, &seeds)?.0
+        },
+        _ => return Err(anyhow!("Provide either an account pubkey or --pda <ACCOUNT_NAME>")),
+    };
+
+    let mut buf = vec![];
+    File::open(Path::new(&args.json)).and_then(|mut file| file.read_to_end(&mut buf))?;
+    let values: Value = serde_json::from_slice(&buf)?;
+
+    ctx.create_account(CreatedAccount {
+        pubkey,
+        program_id: *program_id,
+        account_type: args.account_type.clone(),
+        values,
+        space: args.space,
+    })?;
+
+    println!("✅ {} account created at {}", args.account_type, pubkey);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::{common::diff::DiffStatus, Network, Valid8Context};
+
+pub fn diff(ctx: &Valid8Context, pubkeys: &[String], network: &Option<Network>, json: bool) -> Result<()> {
+    let pubkeys = pubkeys.iter().map(|pubkey| ctx.resolve(pubkey)).collect::<Result<Vec<Pubkey>>>()?;
+    let diffs = ctx.diff(&pubkeys, network.as_ref())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diffs)?);
+        return Ok(())
+    }
+    for diff in diffs.iter() {
+        match diff.status {
+            DiffStatus::Unchanged => println!("✅ {} unchanged on {}", ctx.display_name(&diff.pubkey), diff.network),
+            DiffStatus::Closed => println!("❌ {} doesn't exist on {} anymore", ctx.display_name(&diff.pubkey), diff.network),
+            DiffStatus::Changed => {
+                let account_type = diff.account_type.as_ref().map_or(String::new(), |account_type| format!(" ({})", account_type));
+                println!("🔄 {}{} changed on {} at slot {}:", ctx.display_name(&diff.pubkey), account_type, diff.network, diff.slot);
+                for change in diff.changes.iter() {
+                    let overridden = if change.overridden { " (override)" } else { "" };
+                    println!("    {}: {} -> {}{}", change.field, change.local, change.live, overridden);
+                }
+            },
+        }
+    }
+    let changed = diffs.iter().filter(|diff| diff.status != DiffStatus::Unchanged).count();
+    println!("{} accounts compared, {} changed", diffs.len(), changed);
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
 use dialoguer::Select;
 
-use crate::{program, account, context::Valid8Context};
+use valid8::Valid8Context;
+
+use crate::{program, account};
 
 pub fn edit(ctx: &mut Valid8Context) -> Result<()> {
     let items = vec![
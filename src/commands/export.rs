@@ -0,0 +1,24 @@
+use anyhow::{anyhow, Result};
+
+use valid8::{export::{self, ExportFormat}, Valid8Context};
+
+pub fn export(ctx: &Valid8Context, format: ExportFormat, out: &str, tags: &[String]) -> Result<()> {
+    let report = match tags.is_empty() {
+        true => export::export(ctx, format, out)?,
+        false => {
+            let tagged = ctx.tagged(tags)?;
+            if tagged.programs.is_empty() && tagged.accounts.is_empty() {
+                return Err(anyhow!("No accounts or programs tagged {}", tags.join(", ")))
+            }
+            export::export(&tagged, format, out)?
+        },
+    };
+
+    println!("✅ Exported {} accounts and {} programs to {}", report.accounts, report.programs, out);
+    match (format, report.entrypoint) {
+        (ExportFormat::ValidatorJson, Some(script)) => println!("Start a validator with: {}", script.display()),
+        (ExportFormat::AnchorToml, Some(anchor_toml)) => println!("Copy the sections of {} to your Anchor.toml", anchor_toml.display()),
+        _ => {},
+    }
+    Ok(())
+}
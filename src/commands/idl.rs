@@ -0,0 +1,28 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use valid8::{common::idl::DiscriminatorStrategy, Valid8Context};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum IdlCommand {
+    /// Register a local IDL file for a program
+    Add { program_id: String, path: String },
+    /// Set how account types of a program are identified
+    Discriminator { program_id: String, strategy: DiscriminatorStrategy },
+}
+
+pub fn idl(ctx: &mut Valid8Context, command: &IdlCommand) -> Result<()> {
+    match command {
+        IdlCommand::Add { program_id, path } => {
+            let program_id = &ctx.resolve(program_id)?;
+            ctx.add_local_idl(program_id, path)?;
+            println!("✅ IDL for {} added from {}", program_id, path);
+        }
+        IdlCommand::Discriminator { program_id, strategy } => {
+            let program_id = &ctx.resolve(program_id)?;
+            ctx.set_discriminator_strategy(program_id, *strategy)?;
+            println!("✅ Discriminator strategy for {} set to {:?}", program_id, strategy);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::{fs, path::{Path, PathBuf}};
+use anyhow::{anyhow, Result};
+
+use valid8::{AccountSchema, Network, Valid8Context};
+
+pub fn import(ctx: &mut Valid8Context, paths: &[String]) -> Result<()> {
+    // Directories are imported with all of their json files
+    let mut files: Vec<PathBuf> = vec![];
+    for path in paths.iter().map(Path::new) {
+        if path.is_dir() {
+            let mut dir_files = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<PathBuf>, _>>()?
+                .into_iter()
+                .filter(|file| file.extension().is_some_and(|ext| ext == "json"))
+                .collect::<Vec<PathBuf>>();
+            dir_files.sort();
+            files.append(&mut dir_files);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    for file in files.iter() {
+        let account = AccountSchema::from_json(&fs::read(file)?, &Network::Imported)
+            .map_err(|e| anyhow!("Failed to import {}: {}", file.display(), e))?;
+        match ctx.has_account(&account.pubkey) {
+            true => println!("{} imported from {}, replacing the added account", account.pubkey, file.display()),
+            false => println!("{} imported from {}", account.pubkey, file.display()),
+        }
+        ctx.import_account(account)?;
+    }
+
+    println!("✅ {} accounts imported", files.len());
+    Ok(())
+}
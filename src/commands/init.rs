@@ -0,0 +1,27 @@
+use std::path::Path;
+use anyhow::Result;
+
+use valid8::{common::anchor_toml, Valid8Context};
+
+pub fn init(ctx: &mut Valid8Context, from_anchor: &Option<String>) -> Result<()> {
+    // The config itself is created on startup if it doesn't exist yet
+    if let Some(path) = from_anchor {
+        let report = anchor_toml::add_anchor_toml(ctx, Path::new(path))?;
+        for pubkey in report.skipped.iter() {
+            println!("{} already added", pubkey);
+        }
+        for (pubkey, network) in report.cloned.iter() {
+            println!("{} cloned from {}", pubkey, network);
+        }
+        for (pubkey, filename) in report.imported.iter() {
+            println!("{} imported from {}", pubkey, filename.display());
+        }
+        for (program_id, program) in report.programs.iter() {
+            println!("{} added from {}", program_id, program.display());
+        }
+        println!("✅ {} imported to {}", path, ctx.project_name.to_config());
+    } else {
+        println!("✅ {} initialized", ctx.project_name.to_config());
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::Valid8Context;
+
+pub fn label(ctx: &mut Valid8Context, pubkey: &str, label: &Option<String>) -> Result<()> {
+    let pubkey = ctx.resolve(pubkey)?;
+    ctx.set_label(&pubkey, label.clone())?;
+    match label {
+        Some(label) => println!("✅ {} labeled {}", pubkey, label),
+        None => println!("✅ Label of {} removed", pubkey),
+    }
+    Ok(())
+}
+
+pub fn tag(ctx: &mut Valid8Context, pubkey: &str, tags: &[String], remove: bool) -> Result<()> {
+    let pubkey = ctx.resolve(pubkey)?;
+    match remove {
+        true => {
+            let removed = ctx.remove_tags(&pubkey, tags)?;
+            println!("✅ Removed {} tags from {}", removed, ctx.display_name(&pubkey));
+        },
+        false => {
+            ctx.add_tags(&pubkey, tags)?;
+            println!("✅ {} tagged {}", ctx.display_name(&pubkey), ctx.tags(&pubkey).join(", "));
+        },
+    }
+    Ok(())
+}
+
+fn list_entry(ctx: &Valid8Context, pubkey: &Pubkey, description: String) {
+    let tags = ctx.tags(pubkey);
+    match tags.is_empty() {
+        true => println!("    {} {}", ctx.display_name(pubkey), description),
+        false => println!("    {} {} [{}]", ctx.display_name(pubkey), description, tags.join(", ")),
+    }
+}
+
+pub fn list(ctx: &Valid8Context, tags: &[String]) -> Result<()> {
+    let ctx = match tags.is_empty() {
+        true => ctx.clone(),
+        false => ctx.tagged(tags)?,
+    };
+
+    println!("Programs:");
+    for program in ctx.programs.iter() {
+        list_entry(&ctx, &program.pubkey, format!("on {}", program.network));
+    }
+    println!("Accounts:");
+    for account in ctx.accounts.iter() {
+        match ctx.created.iter().flatten().find(|created| created.pubkey == account.pubkey) {
+            Some(created) => list_entry(&ctx, &account.pubkey, format!("created {} of {}", created.account_type, ctx.display_name(&created.program_id))),
+            None => list_entry(&ctx, &account.pubkey, format!("on {}", account.network)),
+        }
+    }
+    println!("{} programs and {} accounts", ctx.programs.len(), ctx.accounts.len());
+    Ok(())
+}
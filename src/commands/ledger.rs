@@ -2,7 +2,7 @@ use std::{fs, path::Path};
 use anyhow::{anyhow, Result};
 use dialoguer::Input;
 
-use crate::context::Valid8Context;
+use valid8::Valid8Context;
 
 pub fn ledger(ctx: Valid8Context, overwrite: &Option<String>) -> Result<()> {
 
@@ -29,6 +29,7 @@ pub fn ledger(ctx: Valid8Context, overwrite: &Option<String>) -> Result<()> {
         println!("Overwiting test-ledger directory");
         fs::remove_dir_all(ledger_path)?;
     }
-    ctx.create_ledger()?;
+    ctx.create_ledger(ledger_path)?;
+    println!("ledger directory created: test-ledger");
     Ok(())
 }
\ No newline at end of file
@@ -8,4 +8,52 @@ pub mod compose;
 pub use compose::*;
 
 pub mod run;
-pub use run::*;
\ No newline at end of file
+pub use run::*;
+
+pub mod idl;
+pub use idl::*;
+
+pub mod pda;
+pub use pda::*;
+
+pub mod create_account;
+pub use create_account::*;
+
+pub mod export;
+pub use export::*;
+
+pub mod import;
+pub use import::*;
+
+pub mod init;
+pub use init::*;
+
+pub mod bundle;
+pub use bundle::*;
+
+pub mod cache;
+pub use cache::*;
+
+pub mod storage;
+pub use storage::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod diff;
+pub use diff::*;
+
+pub mod overrides;
+pub use overrides::*;
+
+pub mod relocate;
+pub use relocate::*;
+
+pub mod rebase;
+pub use rebase::*;
+
+pub mod labels;
+pub use labels::*;
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use valid8::Valid8Context;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum OverridesCommand {
+    /// List all overrides with their original and current values
+    List,
+    /// Remove the overrides of an account, or only of one field, and restore its original values
+    Revert { pubkey: String, field: Option<String> },
+}
+
+pub fn overrides(ctx: &mut Valid8Context, command: &OverridesCommand) -> Result<()> {
+    match command {
+        OverridesCommand::List => {
+            let states = ctx.override_states()?;
+            let mut pubkey = None;
+            for state in states.iter() {
+                if pubkey != Some(state.pubkey) {
+                    println!("{}", ctx.display_name(&state.pubkey));
+                    pubkey = Some(state.pubkey);
+                }
+                let original = state.original.as_deref().unwrap_or("unknown");
+                let current = state.current.as_deref().unwrap_or("unknown");
+                println!("    {}: {} -> {}", state.edit_field.field_name(), original, current);
+            }
+            println!("{} overrides", states.len());
+        }
+        OverridesCommand::Revert { pubkey, field } => {
+            let pubkey = ctx.resolve(pubkey)?;
+            let removed = ctx.revert_override(&pubkey, field.as_deref())?;
+            println!("✅ Reverted {} overrides of {}", removed, ctx.display_name(&pubkey));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+
+use valid8::{common::{idl, pda}, Network, Valid8Context};
+
+pub fn parse_seed(s: &str) -> Result<(String, String)> {
+    s.split_once('=')
+        .map(|(path, value)| (path.to_string(), value.to_string()))
+        .ok_or(anyhow!("Invalid seed {}, expected <path>=<value>", s))
+}
+
+pub fn pda(ctx: &mut Valid8Context, program_id: &str, account_name: &str, seeds: &[(String, String)], clone: &Option<Network>) -> Result<()> {
+    let program_id = &ctx.resolve(program_id)?;
+    let idl = idl::open_idl(&ctx.project_name, program_id)?;
+    let seeds: HashMap<String, String> = seeds.iter().cloned().collect();
+
+    let (pubkey, bump) = pda::derive_pda(ctx, &idl, program_id, account_name, &seeds)?;
+    println!("{} PDA: {} (bump {})", account_name, pubkey, bump);
+
+    if let Some(network) = clone {
+        if ctx.has_account(&pubkey) {
+            println!("{} already added", pubkey);
+            return Ok(())
+        }
+        ctx.add_account(network, &pubkey)?;
+        println!("✅ {} cloned from {}", pubkey, network);
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::Valid8Context;
+
+pub fn rebase_program(ctx: &mut Valid8Context, from: &str, to: &Pubkey) -> Result<()> {
+    let from = &ctx.resolve(from)?;
+    let report = ctx.rebase_program(from, to)?;
+
+    for (pubkey, address) in report.relocated.iter() {
+        println!("    {} -> {}", ctx.display_name(pubkey), address);
+    }
+    for pubkey in report.not_derived.iter() {
+        println!("⚠️  {} keeps its address, no PDA seeds found for it in the IDL of {}", ctx.display_name(pubkey), from);
+    }
+    if report.program_relocated {
+        println!("    {} -> {} with its program data", from, to);
+    }
+    println!("✅ Rebased {} on {}: {} accounts owned by it, {} PDAs derived again", from, to, report.owners.len(), report.relocated.len());
+    Ok(())
+}
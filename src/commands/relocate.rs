@@ -0,0 +1,18 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::Valid8Context;
+
+pub fn relocate(ctx: &mut Valid8Context, pubkey: &str, to: &Pubkey, rewrite_references: bool, rewrite_programs: bool) -> Result<()> {
+    let pubkey = ctx.resolve(pubkey)?;
+    let rewrites = ctx.relocate_account(&pubkey, to, rewrite_references, rewrite_programs)?;
+    match rewrite_references {
+        true => println!("✅ {} relocated to {}, references to it are rewritten", pubkey, to),
+        false => println!("✅ {} relocated to {}", pubkey, to),
+    }
+    // Counts include the references of earlier relocations, they're all applied together
+    for (account, replaced) in rewrites.iter() {
+        println!("    {} references replaced in {}", replaced, ctx.display_name(account));
+    }
+    Ok(())
+}
@@ -2,7 +2,9 @@ use anyhow::Result;
 use dialoguer::Select;
 use anyhow::anyhow;
 
-use crate::{program, account, context::Valid8Context, commands};
+use valid8::Valid8Context;
+
+use crate::{program, account, commands};
 
 
 pub fn run(mut ctx: Valid8Context) -> Result<()> {
@@ -26,7 +28,7 @@ pub fn run(mut ctx: Valid8Context) -> Result<()> {
             1 => account::clone(&mut ctx)?,
             2 => program::edit(&mut ctx)?,
             3 => account::edit(&mut ctx)?,
-            4 => commands::compose(ctx)?,
+            4 => commands::compose(ctx, None, false, &[])?,
             5 => commands::ledger(ctx, &None)?,
             _ => return Err(anyhow!("Invalid option."))
         }
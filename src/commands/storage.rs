@@ -0,0 +1,24 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use valid8::{common::storage::Storage, Valid8Context};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum StorageCommand {
+    /// Convert all stored accounts to another storage format
+    Migrate {
+        #[arg(value_enum)]
+        storage: Storage,
+    },
+}
+
+pub fn storage(ctx: &mut Valid8Context, command: &StorageCommand) -> Result<()> {
+    match command {
+        StorageCommand::Migrate{storage} => {
+            let from = ctx.storage();
+            let migrated = ctx.migrate_storage(*storage)?;
+            println!("✅ Migrated {} accounts from {:?} to {:?} storage", migrated, from, storage);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::Valid8Context;
+
+pub fn update(ctx: &mut Valid8Context, pubkeys: &[String]) -> Result<()> {
+    let pubkeys = pubkeys.iter().map(|pubkey| ctx.resolve(pubkey)).collect::<Result<Vec<Pubkey>>>()?;
+    let updated = ctx.update(&pubkeys)?;
+
+    for (pubkey, changes) in updated.iter() {
+        match changes.is_empty() {
+            true => println!("    {} unchanged", ctx.display_name(pubkey)),
+            false => println!("🔄 {}: {}", ctx.display_name(pubkey), changes.join(", ")),
+        }
+    }
+    let changed = updated.iter().filter(|(_, changes)| !changes.is_empty()).count();
+    println!("✅ {} accounts updated, {} changed, saved {}", updated.len(), changed, ctx.project_name.to_lockfile());
+    Ok(())
+}
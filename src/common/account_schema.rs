@@ -1,4 +1,7 @@
-use anyhow::Result;
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde_json::{json, Value};
 use serde::{Serialize, Deserialize};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::account::Account;
@@ -37,16 +40,48 @@ impl AccountSchema {
         })
     }
 
-    pub fn to_account(self) -> Result<Account> {
+    pub fn to_account(&self) -> Result<Account> {
         Ok(Account {
             lamports: self.lamports,
-            data: self.data,
+            data: self.data.clone(),
             owner: self.owner,
             executable: self.executable,
             rent_epoch: self.rent_epoch,
         })
     }
 
+    /// Reads an account from `solana account --output json`, Anchor test validator or exported account json
+    pub fn from_json(json: &[u8], network: &Network) -> Result<Self> {
+        let value: Value = serde_json::from_slice(json)?;
+        let pubkey = Pubkey::from_str(value["pubkey"].as_str().ok_or(anyhow!("Missing pubkey"))?)?;
+        let account = &value["account"];
+
+        // data is [data, encoding], or a base58 string in the legacy binary format
+        let data = match &account["data"] {
+            Value::String(data) => bs58::decode(data).into_vec()?,
+            Value::Array(data) => {
+                let raw = data.first().and_then(|d| d.as_str()).ok_or(anyhow!("Missing account data"))?;
+                match data.get(1).and_then(|e| e.as_str()).unwrap_or("base64") {
+                    "base64" => base64::engine::general_purpose::STANDARD.decode(raw)?,
+                    "base58" => bs58::decode(raw).into_vec()?,
+                    "base64+zstd" => zstd::decode_all(&base64::engine::general_purpose::STANDARD.decode(raw)?[..])?,
+                    encoding => return Err(anyhow!("Unsupported account data encoding: {}", encoding)),
+                }
+            },
+            _ => return Err(anyhow!("Missing account data")),
+        };
+
+        Ok(Self {
+            pubkey,
+            network: network.clone(),
+            lamports: account["lamports"].as_u64().ok_or(anyhow!("Missing lamports"))?,
+            data,
+            owner: Pubkey::from_str(account["owner"].as_str().ok_or(anyhow!("Missing owner"))?)?,
+            executable: account["executable"].as_bool().unwrap_or_default(),
+            rent_epoch: account["rentEpoch"].as_u64().unwrap_or_default(),
+        })
+    }
+
     pub fn get_program_executable_data_address(&self) -> Result<Pubkey> {
         let mut executable_data_bytes = [0u8;32];
         executable_data_bytes.copy_from_slice(&self.data[4..36]);
@@ -67,23 +102,24 @@ impl AccountSchema {
         // let name
     // }
 
-    // pub fn export(&self) -> Result<Vec<u8>> {
-    //     let mut data_base64 = String::new();
-    //     base64::engine::general_purpose::STANDARD.encode_string(&self.data, &mut data_base64);
-    //     Ok(json!({
-    //         "account": {
-    //             "data":[data_base64,"base64"],
-    //                 "executable":self.executable,
-    //                 "lamports":self.lamports,
-    //                 "owner": self.owner.to_string(),
-    //                 "rentEpoch": self.rent_epoch,
-    //                 "space": self.data.len()
-    //             },
-    //             "pubkey": self.pubkey.to_string(),
-    //             "network": self.network.to_string()
-    //         }
-    //     ).to_string().as_bytes().to_vec())
-    // }
+    pub fn export(&self) -> Result<Vec<u8>> {
+        // RPC style account json, as read by solana-test-validator --account
+        let mut data_base64 = String::new();
+        base64::engine::general_purpose::STANDARD.encode_string(&self.data, &mut data_base64);
+        Ok(serde_json::to_vec_pretty(&json!({
+            "account": {
+                "data":[data_base64,"base64"],
+                    "executable":self.executable,
+                    "lamports":self.lamports,
+                    "owner": self.owner.to_string(),
+                    "rentEpoch": self.rent_epoch,
+                    "space": self.data.len()
+                },
+                "pubkey": self.pubkey.to_string()
+            }
+        ))?)
+    }
+
     // pub fn get_data(&self) -> Result<Vec<u8>> {
     //     let mut buf = vec![];
     //     File::open(Path::new(&self.data_path))
@@ -0,0 +1,122 @@
+use std::{fs, path::{Path, PathBuf}, str::FromStr};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::context::Valid8Context;
+
+use super::{helpers, AccountSchema, Network};
+
+/// The test sections of an Anchor.toml, other sections are ignored
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AnchorToml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<AnchorTest>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AnchorTest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator: Option<AnchorTestValidator>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genesis: Vec<AnchorGenesis>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AnchorTestValidator {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clone: Vec<AnchorClone>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub account: Vec<AnchorAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AnchorClone {
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AnchorAccount {
+    pub address: String,
+    pub filename: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AnchorGenesis {
+    pub address: String,
+    pub program: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upgradeable: Option<bool>,
+}
+
+pub fn read_anchor_toml(path: &Path) -> Result<AnchorToml> {
+    let anchor_toml = fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&anchor_toml).map_err(|e| anyhow!("Invalid Anchor.toml {}: {}", path.display(), e))
+}
+
+pub fn url_to_network(url: &str) -> Network {
+    match url.trim_end_matches('/') {
+        "https://api.mainnet-beta.solana.com" | "mainnet" | "m" => Network::Mainnet,
+        "https://api.devnet.solana.com" | "devnet" | "d" => Network::Devnet,
+        "http://localhost:8899" | "http://127.0.0.1:8899" | "localhost" | "l" => Network::Local,
+        url => Network::Custom(url.to_string()),
+    }
+}
+
+/// What was added from an Anchor.toml
+#[derive(Debug, Default)]
+pub struct AnchorTomlReport {
+    pub cloned: Vec<(Pubkey, Network)>,
+    /// Clones that were already in the config
+    pub skipped: Vec<Pubkey>,
+    pub imported: Vec<(Pubkey, PathBuf)>,
+    pub programs: Vec<(Pubkey, PathBuf)>,
+}
+
+/// Adds the clones, accounts and genesis programs of an Anchor.toml to a context, file paths are relative to the Anchor.toml
+pub fn add_anchor_toml(ctx: &mut Valid8Context, path: &Path) -> Result<AnchorTomlReport> {
+    let anchor_toml = read_anchor_toml(path)?;
+    let workspace = path.parent().unwrap_or(Path::new("."));
+    let test = anchor_toml.test.unwrap_or_default();
+    let validator = test.validator.unwrap_or_default();
+
+    // Clones are fetched from the test validator url, like anchor test does
+    let network = validator.url.as_deref().map_or(Network::Mainnet, url_to_network);
+    let mut report = AnchorTomlReport::default();
+    for clone in validator.clone.iter() {
+        let pubkey = Pubkey::from_str(&clone.address)?;
+        if ctx.has_program(&pubkey) || ctx.has_account(&pubkey) {
+            report.skipped.push(pubkey);
+            continue
+        }
+        // The account is fetched once, it decides whether the clone is added as program or account
+        let (account, slot) = helpers::fetch_account_with_slot(&network, &pubkey, ctx.offline)?;
+        match account.executable {
+            true => ctx.add_fetched_program(account, slot)?,
+            false => ctx.add_fetched_account(account, slot)?,
+        }
+        report.cloned.push((pubkey, network.clone()));
+    }
+
+    for account in validator.account.iter() {
+        let filename = workspace.join(&account.filename);
+        let account_schema = AccountSchema::from_json(&fs::read(&filename)?, &Network::Imported)
+            .map_err(|e| anyhow!("Failed to import {}: {}", filename.display(), e))?;
+        if account_schema.pubkey.to_string() != account.address {
+            return Err(anyhow!("Account {} in {} doesn't match address {}", account_schema.pubkey, filename.display(), account.address))
+        }
+        report.imported.push((account_schema.pubkey, filename));
+        ctx.import_account(account_schema)?;
+    }
+
+    for genesis in test.genesis.iter() {
+        let program = workspace.join(&genesis.program);
+        let elf = fs::read(&program).map_err(|e| anyhow!("Failed to read {}: {}", program.display(), e))?;
+        let program_id = Pubkey::from_str(&genesis.address)?;
+        ctx.add_local_program(&program_id, &elf, genesis.upgradeable.unwrap_or_default())?;
+        report.programs.push((program_id, program));
+    }
+    Ok(report)
+}
@@ -0,0 +1,226 @@
+use std::{collections::BTreeMap, fs::{self, File}, io::Read, path::{Component, Path, PathBuf}};
+use anyhow::{anyhow, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::hash;
+
+use super::{project_name::ProjectName, storage};
+
+pub const BUNDLE_FORMAT: &str = "valid8-bundle";
+pub const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Lists every file of a bundle with its sha256 hash, checked on unpack
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    pub valid8_version: String,
+    pub project_name: ProjectName,
+    pub files: BTreeMap<String, String>,
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Packs the config and resources directory of a project into a gzip compressed tar archive
+pub fn pack(project_name: &ProjectName, out: &Path) -> Result<BundleManifest> {
+    let mut files = vec![PathBuf::from(project_name.to_config())];
+    collect_files(Path::new(&project_name.to_resources()), &mut files)?;
+    files.sort();
+
+    // Cache pointers are replaced by the account they point to, so bundles are self contained
+    let contents = files
+        .iter()
+        .map(|file| match storage::is_cache_pointer(file) {
+            true => {
+                let account = storage::read_account(project_name, &storage::pointer_pubkey(file)?)?;
+                Ok((format!("{}{}.bin", project_name.to_resources(), account.pubkey), bincode::serialize(&account)?))
+            },
+            false => Ok((bundle_path(file)?, fs::read(file)?)),
+        })
+        .collect::<Result<Vec<(String, Vec<u8>)>>>()?;
+
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        valid8_version: env!("CARGO_PKG_VERSION").to_string(),
+        project_name: project_name.clone(),
+        files: contents.iter().map(|(path, data)| (path.clone(), hash(data).to_string())).collect(),
+    };
+
+    // The manifest is the first entry, so it can be read without unpacking everything
+    let mut archive = tar::Builder::new(GzEncoder::new(File::create(out)?, Compression::best()));
+    append_file(&mut archive, MANIFEST_NAME, &serde_json::to_vec_pretty(&manifest)?)?;
+    for (path, data) in contents.iter() {
+        append_file(&mut archive, path, data)?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+fn bundle_path(file: &Path) -> Result<String> {
+    file.to_str()
+        .map(|path| path.replace('\\', "/"))
+        .ok_or(anyhow!("Invalid file name {}", file.display()))
+}
+
+fn append_file<W: std::io::Write>(archive: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// Reads and verifies a bundle, returning its manifest and files
+pub fn read_bundle(path: &Path) -> Result<(BundleManifest, BTreeMap<String, Vec<u8>>)> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut files = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_path_buf();
+        // Never write outside of the current directory
+        if entry_path.components().any(|component| !matches!(component, Component::Normal(_) | Component::CurDir)) {
+            return Err(anyhow!("Invalid path in bundle: {}", entry_path.display()));
+        }
+        let mut data = vec![];
+        entry.read_to_end(&mut data)?;
+        files.insert(bundle_path(&entry_path)?, data);
+    }
+
+    let manifest: BundleManifest = serde_json::from_slice(
+        &files.remove(MANIFEST_NAME).ok_or(anyhow!("No manifest found, {} is not a valid8 bundle", path.display()))?
+    )?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(anyhow!("Unknown bundle format {}", manifest.format));
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(anyhow!("Bundle version {} is newer than supported version {}, please update valid8", manifest.version, BUNDLE_VERSION));
+    }
+    // Only the config and the resources directory of the project are restored
+    let (config, resources) = (manifest.project_name.to_config(), manifest.project_name.to_resources());
+    if let Some(file) = files.keys().find(|file| **file != config && !file.starts_with(&resources)) {
+        return Err(anyhow!("File {} is not part of project {}", file, manifest.project_name));
+    }
+
+    for (file, expected) in manifest.files.iter() {
+        let data = files.get(file).ok_or(anyhow!("File {} of the manifest is missing from the bundle", file))?;
+        let actual = hash(data).to_string();
+        if actual != *expected {
+            return Err(anyhow!("Hash mismatch for {}: expected {}, found {}", file, expected, actual));
+        }
+    }
+    if let Some(file) = files.keys().find(|file| !manifest.files.contains_key(*file)) {
+        return Err(anyhow!("File {} is not listed in the bundle manifest", file));
+    }
+    Ok((manifest, files))
+}
+
+/// Restores the config and resources of a bundle into the current directory
+pub fn unpack(path: &Path, overwrite: bool) -> Result<BundleManifest> {
+    let (manifest, files) = read_bundle(path)?;
+
+    let config = manifest.project_name.to_config();
+    if Path::new(&config).exists() && !overwrite {
+        return Err(anyhow!("{} already exists, use --force to overwrite it", config));
+    }
+
+    // Stale resources of the same project would mix with the bundle, a symlinked directory outside of the
+    // current directory is never removed
+    let resources = manifest.project_name.to_resources();
+    if Path::new(&resources).exists() {
+        let cwd = std::env::current_dir()?.canonicalize()?;
+        let resources_path = Path::new(&resources).canonicalize()?;
+        if resources_path == cwd || !resources_path.starts_with(&cwd) {
+            return Err(anyhow!("{} resolves to {}, outside of {}", resources, resources_path.display(), cwd.display()));
+        }
+        fs::remove_dir_all(&resources_path)?;
+    }
+    for (file, data) in files.iter() {
+        let file = Path::new(file);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(file, data)?;
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    pub(crate) fn write_bundle(path: &Path, manifest: Value, files: &[(&str, &[u8])]) {
+        let mut archive = tar::Builder::new(GzEncoder::new(File::create(path).unwrap(), Compression::fast()));
+        append_file(&mut archive, MANIFEST_NAME, &serde_json::to_vec(&manifest).unwrap()).unwrap();
+        for (file, data) in files.iter() {
+            append_file(&mut archive, file, data).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap();
+    }
+
+    pub(crate) fn manifest(project_name: &str, files: &[(&str, &[u8])]) -> Value {
+        json!({
+            "format": BUNDLE_FORMAT,
+            "version": BUNDLE_VERSION,
+            "valid8_version": "0.0.0",
+            "project_name": { "name": project_name },
+            "files": files.iter().map(|(file, data)| (file.to_string(), hash(data).to_string())).collect::<BTreeMap<String, String>>(),
+        })
+    }
+
+    #[test]
+    fn reads_project_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.v8");
+        let files: &[(&str, &[u8])] = &[("fixtures.json", b"{}"), (".fixtures/account.bin", b"data")];
+        write_bundle(&path, manifest("fixtures", files), files);
+
+        let (manifest, read) = read_bundle(&path).unwrap();
+        assert_eq!(manifest.project_name.to_string(), "fixtures");
+        assert_eq!(read.get(".fixtures/account.bin").map(Vec::as_slice), Some(b"data".as_slice()));
+    }
+
+    #[test]
+    fn rejects_project_names_with_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("evil.v8");
+        for project_name in ["../evil", "a/b", "a\\b", ".hidden", "", ".."] {
+            write_bundle(&path, manifest(project_name, &[]), &[]);
+            assert!(read_bundle(&path).is_err(), "{:?} was accepted", project_name);
+        }
+    }
+
+    #[test]
+    fn rejects_files_outside_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("evil.v8");
+        let files: &[(&str, &[u8])] = &[("fixtures.json", b"{}"), ("other/file", b"data")];
+        write_bundle(&path, manifest("fixtures", files), files);
+
+        let error = read_bundle(&path).unwrap_err().to_string();
+        assert!(error.contains("other/file"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("changed.v8");
+        write_bundle(&path, manifest("fixtures", &[("fixtures.json", b"{}")]), &[("fixtures.json", b"[]")]);
+
+        assert!(read_bundle(&path).unwrap_err().to_string().contains("Hash mismatch"));
+    }
+}
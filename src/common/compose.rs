@@ -0,0 +1,651 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt::Display, fs, path::{Component, Path, PathBuf}};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{bpf_loader_upgradeable, pubkey::Pubkey};
+
+use crate::{config::{ConfigEntry, ConfigJson}, context::{CreatedAccount, EditField, Override}};
+
+use super::{lockfile::{LockedSource, Lockfile}, project_name::ProjectName, remote, storage, Network};
+
+/// A config to include, a local path or a remote fixture set fetched into the cache
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ComposeSource {
+    /// Path relative to the including config
+    Path(String),
+    /// Config at `path` in a git repository at revision `rev`
+    Git {
+        git: String,
+        rev: String,
+        #[serde(default = "default_config_path")]
+        path: String,
+    },
+    /// Bundle created with `valid8 pack`, a relative path, `file://` or http(s) url
+    Bundle { bundle: String },
+}
+
+fn default_config_path() -> String {
+    ProjectName::default().to_config()
+}
+
+impl Display for ComposeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComposeSource::Path(path) => write!(f, "{}", path),
+            ComposeSource::Git { git, rev, path } => write!(f, "{}#{}:{}", git, rev, path),
+            ComposeSource::Bundle { bundle } => write!(f, "{}", bundle),
+        }
+    }
+}
+
+/// A config included by compose, with the path it was read from
+#[derive(Debug)]
+pub struct ComposedConfig {
+    /// The compose entry, or the path for local includes
+    pub source: String,
+    pub path: PathBuf,
+    pub config: ConfigJson,
+}
+
+impl ComposedConfig {
+    /// Resources directory next to the config, with accounts and IDLs of local fixtures
+    pub fn resources(&self) -> PathBuf {
+        self.path.parent().unwrap_or(Path::new("")).join(self.config.project_name.to_resources())
+    }
+}
+
+struct Resolver {
+    // Canonical and displayed path of each config from the root to the one being resolved
+    stack: Vec<(PathBuf, String)>,
+    visited: HashSet<PathBuf>,
+    resolved: Vec<ComposedConfig>,
+    lock: Lockfile,
+    update: bool,
+    offline: bool,
+    locked: Lockfile,
+}
+
+/// Reads all configs included by `root` directly or indirectly, in merge order.
+/// Includes are relative to the including file and are ordered depth first, each config before its own includes,
+/// so a config included twice is only read once and the first definition of an account always wins.
+/// Remote sources use the version pinned in `lock` unless `update` is set, returns the configs with the new lockfile.
+pub fn resolve(root_path: &Path, root: &ConfigJson, lock: &Lockfile, update: bool, offline: bool) -> Result<(Vec<ComposedConfig>, Lockfile)> {
+    let canonical = fs::canonicalize(root_path).map_err(|e| anyhow!("{}: {}", root_path.display(), e))?;
+    let mut resolver = Resolver {
+        stack: vec![(canonical.clone(), root_path.display().to_string())],
+        visited: HashSet::from([canonical]),
+        resolved: vec![],
+        lock: lock.clone(),
+        update,
+        offline,
+        locked: Lockfile { compose: BTreeMap::new(), ..lock.clone() },
+    };
+    resolver.visit(root_path, root)?;
+    Ok((resolver.resolved, resolver.locked))
+}
+
+// The config path of a git source is relative to the checkout and can't leave it, not even through a symlink
+fn checkout_path(checkout: &Path, path: &str) -> Result<PathBuf> {
+    if Path::new(path).components().any(|component| !matches!(component, Component::Normal(_) | Component::CurDir)) {
+        return Err(anyhow!("Invalid path {}, it must be relative to the repository and can't contain ..", path))
+    }
+    let config_path = checkout.join(path);
+    let canonical = fs::canonicalize(&config_path).map_err(|e| anyhow!("{}: {}", config_path.display(), e))?;
+    if !canonical.starts_with(fs::canonicalize(checkout)?) {
+        return Err(anyhow!("{} resolves to {}, outside of the repository", path, canonical.display()))
+    }
+    Ok(config_path)
+}
+
+impl Resolver {
+    fn pinned(&self, id: &str) -> Option<&LockedSource> {
+        self.lock.compose.get(id).filter(|_| !self.update)
+    }
+
+    // Fetches remote sources and returns the local path of the included config
+    fn locate(&mut self, source: &ComposeSource, dir: &Path) -> Result<PathBuf> {
+        match source {
+            ComposeSource::Path(path) => Ok(dir.join(path)),
+            ComposeSource::Git { git, rev, path } => {
+                let id = format!("git+{}#{}", git, rev);
+                let locked_commit = self.pinned(&id).and_then(|locked| locked.commit.clone());
+                let (checkout, commit) = remote::git_checkout(git, rev, locked_commit.as_deref(), self.offline)?;
+                self.locked.compose.insert(id, LockedSource { commit: Some(commit), hash: None });
+                checkout_path(&checkout, path)
+            },
+            ComposeSource::Bundle { bundle } => {
+                let location = match bundle.contains("://") {
+                    true => bundle.clone(),
+                    false => dir.join(bundle).to_string_lossy().into_owned(),
+                };
+                let id = format!("bundle+{}", location);
+                let locked_hash = self.pinned(&id).and_then(|locked| locked.hash.clone());
+                let (config_path, hash) = remote::bundle_checkout(&location, Path::new(""), locked_hash.as_deref(), self.offline)?;
+                self.locked.compose.insert(id, LockedSource { commit: None, hash: Some(hash) });
+                Ok(config_path)
+            },
+        }
+    }
+
+    fn visit(&mut self, path: &Path, config: &ConfigJson) -> Result<()> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for source in config.compose.iter().flatten() {
+            let shown = match source {
+                ComposeSource::Path(include) => dir.join(include).display().to_string(),
+                source => source.to_string(),
+            };
+            let include_path = self.locate(source, dir)
+                .map_err(|e| anyhow!("{} included by {}: {}", shown, path.display(), e))?;
+            let canonical = fs::canonicalize(&include_path)
+                .map_err(|e| anyhow!("{} included by {}: {}", shown, path.display(), e))?;
+
+            if let Some(start) = self.stack.iter().position(|(on_stack, _)| *on_stack == canonical) {
+                let cycle = self.stack[start..]
+                    .iter()
+                    .map(|(_, shown)| shown.clone())
+                    .chain([shown])
+                    .collect::<Vec<String>>()
+                    .join(" -> ");
+                return Err(anyhow!("Compose cycle: {}", cycle))
+            }
+            // Shared includes of a diamond are merged at their first position
+            if !self.visited.insert(canonical.clone()) {
+                continue
+            }
+
+            let (included, _) = ConfigJson::parse(&fs::read(&include_path)?)
+                .map_err(|e| anyhow!("{}: {}", shown, e))?;
+            let position = self.resolved.len();
+            self.stack.push((canonical, shown.clone()));
+            self.visit(&include_path, &included)?;
+            self.stack.pop();
+            self.resolved.insert(position, ComposedConfig { source: shown, path: include_path, config: included });
+        }
+        Ok(())
+    }
+}
+
+/// Copies imported accounts, program data and IDLs from the resources of included configs,
+/// so fixture sets install without network access. Imported accounts merged from an include are copied from
+/// the include in `sources`, others only if they're missing. Returns the number of copied files.
+pub fn copy_resources(config: &ConfigJson, included: &[ComposedConfig], sources: &HashMap<String, String>) -> Result<usize> {
+    let resources = PathBuf::from(config.project_name.to_resources());
+    fs::create_dir_all(&resources)?;
+    let mut copied = 0;
+
+    let imported_pubkeys = config.programs
+        .iter()
+        .chain(config.accounts.iter())
+        .filter(|entry| entry.network == Network::Imported)
+        .map(|entry| entry.pubkey);
+    for pubkey in imported_pubkeys {
+        let current = storage::read_account_from(&resources, &pubkey).ok();
+        let candidates: Vec<&ComposedConfig> = match sources.get(&pubkey.to_string()) {
+            Some(source) => included.iter().filter(|composed| composed.source == *source).collect(),
+            None if current.is_some() => continue,
+            None => included.iter().collect(),
+        };
+        for composed in candidates {
+            if let Ok(account) = storage::read_account_from(&composed.resources(), &pubkey) {
+                if current.as_ref() == Some(&account) {
+                    break
+                }
+                // Upgradeable programs need their program data account as well
+                if account.executable && account.owner == bpf_loader_upgradeable::id() {
+                    let program_data = storage::read_account_from(&composed.resources(), &account.get_program_executable_data_address()?)?;
+                    storage::save_account(&config.project_name, config.storage(), config.discriminators.as_ref(), &program_data)?;
+                    copy_pristine(config, composed, &program_data.pubkey)?;
+                    copied += 1;
+                }
+                storage::save_account(&config.project_name, config.storage(), config.discriminators.as_ref(), &account)?;
+                copy_pristine(config, composed, &pubkey)?;
+                copied += 1;
+                break
+            }
+        }
+    }
+
+    for program_id in config.idls.iter() {
+        let idl_name = format!("{}.idl.json", program_id);
+        if resources.join(&idl_name).exists() {
+            continue
+        }
+        if let Some(idl) = included.iter().map(|composed| composed.resources().join(&idl_name)).find(|idl| idl.exists()) {
+            fs::copy(idl, resources.join(&idl_name))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+fn copy_pristine(config: &ConfigJson, composed: &ComposedConfig, pubkey: &Pubkey) -> Result<()> {
+    // Overrides of copied accounts can only be reverted with the original of the include
+    if let Some(pristine) = storage::read_pristine_from(&composed.resources(), pubkey)? {
+        storage::save_pristine(&config.project_name, &pristine)?;
+    }
+    Ok(())
+}
+
+/// Which definition is kept when configs define the same account, override, IDL or discriminator differently
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MergePolicy {
+    /// Keep the definition merged first, so a config wins over its includes
+    #[default]
+    Base,
+    /// Replace it with the definition of the included config
+    Include,
+    /// Fail with the conflict report
+    Error,
+}
+
+/// Two different definitions of the same thing, with the configs they came from
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub key: String,
+    pub base: String,
+    pub base_source: String,
+    pub include: String,
+    pub include_source: String,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} ({}) vs {} ({})", self.key, self.base, self.base_source, self.include, self.include_source)
+    }
+}
+
+/// Result of merging all includes of a config
+#[derive(Debug, Default)]
+pub struct ComposeReport {
+    pub configs: usize,
+    pub accounts: u32,
+    pub programs: u32,
+    /// Accounts and IDLs copied from the resources of included configs
+    pub copied: usize,
+    pub conflicts: Vec<Conflict>,
+    /// The included config each merged definition came from, by pubkey or conflict key, base definitions aren't listed
+    pub sources: HashMap<String, String>,
+}
+
+// How a config defines an account, metadata like labels isn't part of the definition
+#[derive(Debug, Clone, PartialEq)]
+enum Definition {
+    Program(ConfigEntry),
+    Account(ConfigEntry),
+    Created(CreatedAccount),
+}
+
+impl Definition {
+    fn find(config: &ConfigJson, pubkey: &Pubkey) -> Option<Self> {
+        config.programs.iter().find(|entry| entry.pubkey == *pubkey).cloned().map(Definition::Program)
+            .or_else(|| config.accounts.iter().find(|entry| entry.pubkey == *pubkey).cloned().map(Definition::Account))
+            .or_else(|| config.created.iter().flatten().find(|created| created.pubkey == *pubkey).cloned().map(Definition::Created))
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Definition::Program(a), Definition::Program(b)) | (Definition::Account(a), Definition::Account(b)) => a.network == b.network,
+            (a, b) => a == b,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Definition::Program(entry) => format!("program on {}", entry.network),
+            Definition::Account(entry) => format!("account on {}", entry.network),
+            Definition::Created(created) => format!("created {} account {}", created.account_type, truncate(&created.values.to_string())),
+        }
+    }
+
+    fn remove(config: &mut ConfigJson, pubkey: &Pubkey) {
+        config.programs.retain(|entry| entry.pubkey != *pubkey);
+        config.accounts.retain(|entry| entry.pubkey != *pubkey);
+        if let Some(created) = config.created.as_mut() {
+            created.retain(|created| created.pubkey != *pubkey);
+        }
+    }
+
+    fn add(self, config: &mut ConfigJson) {
+        match self {
+            Definition::Program(entry) => config.programs.push(entry),
+            Definition::Account(entry) => config.accounts.push(entry),
+            Definition::Created(created) => config.created.get_or_insert_with(Vec::new).push(created),
+        }
+    }
+}
+
+fn truncate(description: &str) -> String {
+    match description.char_indices().nth(60) {
+        Some((end, _)) => format!("{}...", &description[..end]),
+        None => description.to_string(),
+    }
+}
+
+fn edit_fields(config: &ConfigJson, pubkey: &Pubkey) -> Vec<EditField> {
+    config.overrides.iter().flatten().filter(|over| over.pubkey == *pubkey).flat_map(|over| over.edit_fields.clone()).collect()
+}
+
+/// Merges included configs into a base config, following a merge policy for conflicts
+pub struct Merger {
+    pub config: ConfigJson,
+    policy: MergePolicy,
+    base_source: String,
+    report: ComposeReport,
+}
+
+impl Merger {
+    pub fn new(config: ConfigJson, base_path: &Path, policy: MergePolicy) -> Self {
+        let base_source = base_path.display().to_string();
+        Self { config, policy, base_source, report: ComposeReport::default() }
+    }
+
+    /// Returns the merged config and report, or the report as an error with the error policy
+    pub fn finish(self) -> Result<(ConfigJson, ComposeReport)> {
+        if self.policy == MergePolicy::Error && !self.report.conflicts.is_empty() {
+            let conflicts = self.report.conflicts.iter().map(|conflict| format!("    {}", conflict)).collect::<Vec<String>>().join("\n");
+            return Err(anyhow!("{} compose conflicts:\n{}", self.report.conflicts.len(), conflicts))
+        }
+        Ok((self.config, self.report))
+    }
+
+    // Records a conflict and returns whether the included definition replaces the current one
+    fn conflict(&mut self, key: String, base: String, include: String, include_source: &str) -> bool {
+        let base_source = self.report.sources.get(&key).cloned().unwrap_or(self.base_source.clone());
+        self.report.conflicts.push(Conflict { key: key.clone(), base, base_source, include, include_source: include_source.to_string() });
+        let replace = self.policy == MergePolicy::Include;
+        if replace {
+            self.report.sources.insert(key, include_source.to_string());
+        }
+        replace
+    }
+
+    /// Adds an included config, conflicting definitions are kept or replaced following the policy
+    pub fn merge(&mut self, included: &ComposedConfig) {
+        let ComposedConfig { source, path, config } = included;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        self.report.configs += 1;
+        self.config.networks.extend(config.networks.iter().cloned());
+
+        let definitions = config.programs.iter().cloned().map(Definition::Program)
+            .chain(config.accounts.iter().cloned().map(Definition::Account))
+            .chain(config.created.iter().flatten().cloned().map(Definition::Created));
+        for definition in definitions {
+            let pubkey = match &definition {
+                Definition::Program(entry) | Definition::Account(entry) => entry.pubkey,
+                Definition::Created(created) => created.pubkey,
+            };
+            match Definition::find(&self.config, &pubkey) {
+                None => {
+                    match definition {
+                        Definition::Program(_) => self.report.programs += 1,
+                        _ => self.report.accounts += 1,
+                    }
+                    self.report.sources.insert(pubkey.to_string(), source.clone());
+                    definition.add(&mut self.config);
+                },
+                Some(current) if current.same_as(&definition) => {},
+                Some(current) => {
+                    if self.conflict(pubkey.to_string(), current.describe(), definition.describe(), source) {
+                        Definition::remove(&mut self.config, &pubkey);
+                        definition.add(&mut self.config);
+                    }
+                },
+            }
+        }
+
+        // All overrides of an account are one definition, they're only applied in the order of a single config
+        let mut seen = HashSet::new();
+        let override_pubkeys: Vec<Pubkey> = config.overrides.iter().flatten().map(|over| over.pubkey).filter(|pubkey| seen.insert(*pubkey)).collect();
+        for pubkey in override_pubkeys {
+            let current = edit_fields(&self.config, &pubkey);
+            let include = edit_fields(config, &pubkey);
+            let describe = |edit_fields: &Vec<EditField>| truncate(&serde_json::to_string(edit_fields).unwrap_or_default());
+            let replace = match current.is_empty() {
+                true => true,
+                false if current == include => false,
+                false => self.conflict(format!("overrides of {}", pubkey), describe(&current), describe(&include), source),
+            };
+            if replace {
+                let overrides = self.config.overrides.get_or_insert_with(Vec::new);
+                overrides.retain(|over| over.pubkey != pubkey);
+                overrides.push(Override { pubkey, edit_fields: include });
+            }
+        }
+
+        for idl in config.idls.iter() {
+            if !self.config.idls.contains(idl) {
+                self.config.idls.push(idl.clone());
+            }
+        }
+        // IDL paths are relative to the included file
+        for (program_id, idl_path) in config.local_idls.iter().flatten() {
+            let idl_path = dir.join(idl_path).to_string_lossy().into_owned();
+            let current = self.config.local_idls.as_ref().and_then(|local_idls| local_idls.get(program_id)).cloned();
+            let replace = match current {
+                None => true,
+                Some(current) if current == idl_path => false,
+                Some(current) => self.conflict(format!("local IDL of {}", program_id), current, idl_path.clone(), source),
+            };
+            if replace {
+                self.config.local_idls.get_or_insert_with(Default::default).insert(program_id.clone(), idl_path);
+            }
+        }
+        for (program_id, strategy) in config.discriminators.iter().flatten() {
+            let current = self.config.discriminators.as_ref().and_then(|discriminators| discriminators.get(program_id)).copied();
+            let replace = match current {
+                None => true,
+                Some(current) if current == *strategy => false,
+                Some(current) => self.conflict(format!("discriminator of {}", program_id), format!("{:?}", current).to_lowercase(), format!("{:?}", strategy).to_lowercase(), source),
+            };
+            if replace {
+                self.config.discriminators.get_or_insert_with(Default::default).insert(program_id.clone(), *strategy);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use serde_json::{json, Value};
+
+    use crate::common::bundle;
+    use super::*;
+
+    fn config(compose: &[&str], accounts: &[(Pubkey, &str)]) -> ConfigJson {
+        let accounts: Vec<Value> = accounts.iter().map(|(pubkey, network)| json!({ "pubkey": pubkey.to_string(), "network": network })).collect();
+        ConfigJson::parse(&serde_json::to_vec(&json!({
+            "version": 4,
+            "project_name": { "name": "valid8" },
+            "networks": [],
+            "programs": [],
+            "accounts": accounts,
+            "idls": [],
+            "compose": compose,
+        })).unwrap()).unwrap().0
+    }
+
+    fn write_config(dir: &Path, name: &str, config: &ConfigJson) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, serde_json::to_vec(config).unwrap()).unwrap();
+        path
+    }
+
+    // Remote sources are fetched into the cache, tests share one temporary cache
+    fn test_cache() {
+        static CACHE: std::sync::OnceLock<tempfile::TempDir> = std::sync::OnceLock::new();
+        CACHE.get_or_init(|| {
+            let cache = tempfile::tempdir().unwrap();
+            std::env::set_var("VALID8_CACHE_DIR", cache.path());
+            cache
+        });
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .arg("-C").arg(dir)
+            .args(["-c", "user.name=valid8", "-c", "user.email=valid8@localhost"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    }
+
+    fn resolve_source(dir: &Path, source: ComposeSource, lock: &Lockfile, update: bool) -> Result<(Vec<ComposedConfig>, Lockfile)> {
+        let mut root = config(&[], &[]);
+        root.compose = Some(vec![source]);
+        let root_path = write_config(dir, "root.json", &root);
+        resolve(&root_path, &root, lock, update, true)
+    }
+
+    fn composed(dir: &Path, name: &str, config: ConfigJson) -> ComposedConfig {
+        ComposedConfig { source: name.to_string(), path: dir.join(name), config }
+    }
+
+    #[test]
+    fn reads_diamond_includes_once_in_merge_order() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "left.json", &config(&["shared.json"], &[]));
+        write_config(dir.path(), "right.json", &config(&["shared.json"], &[]));
+        write_config(dir.path(), "shared.json", &config(&[], &[]));
+        let root = config(&["left.json", "right.json"], &[]);
+        let root_path = write_config(dir.path(), "root.json", &root);
+
+        let (resolved, _) = resolve(&root_path, &root, &Lockfile::default(), false, true).unwrap();
+        let names: Vec<String> = resolved.iter().map(|composed| composed.path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["left.json", "shared.json", "right.json"]);
+    }
+
+    #[test]
+    fn rejects_compose_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "a.json", &config(&["b.json"], &[]));
+        write_config(dir.path(), "b.json", &config(&["a.json"], &[]));
+        let root = config(&["a.json"], &[]);
+        let root_path = write_config(dir.path(), "root.json", &root);
+
+        let error = resolve(&root_path, &root, &Lockfile::default(), false, true).unwrap_err().to_string();
+        assert!(error.contains("Compose cycle"), "{}", error);
+        assert!(error.contains("a.json -> ") && error.ends_with("a.json"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_configs_including_themselves() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = config(&["root.json"], &[]);
+        let root_path = write_config(dir.path(), "root.json", &root);
+
+        assert!(resolve(&root_path, &root, &Lockfile::default(), false, true).unwrap_err().to_string().contains("Compose cycle"));
+    }
+
+    #[test]
+    fn merge_policies_resolve_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shared, added) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let network = |config: &ConfigJson| config.accounts.iter().find(|entry| entry.pubkey == shared).unwrap().network.clone();
+
+        for (policy, expected) in [(MergePolicy::Base, Network::Mainnet), (MergePolicy::Include, Network::Devnet)] {
+            let mut merger = Merger::new(config(&[], &[(shared, "mainnet")]), &dir.path().join("root.json"), policy);
+            merger.merge(&composed(dir.path(), "include.json", config(&[], &[(shared, "devnet"), (added, "mainnet")])));
+            let (merged, report) = merger.finish().unwrap();
+            assert_eq!(network(&merged), expected);
+            assert_eq!(merged.accounts.len(), 2);
+            assert_eq!(report.accounts, 1);
+            assert_eq!(report.conflicts.len(), 1);
+            assert_eq!(report.conflicts[0].key, shared.to_string());
+            assert_eq!(report.sources.get(&added.to_string()).map(String::as_str), Some("include.json"));
+            assert_eq!(report.sources.contains_key(&shared.to_string()), policy == MergePolicy::Include);
+        }
+
+        let mut merger = Merger::new(config(&[], &[(shared, "mainnet")]), &dir.path().join("root.json"), MergePolicy::Error);
+        merger.merge(&composed(dir.path(), "include.json", config(&[], &[(shared, "devnet")])));
+        let error = merger.finish().unwrap_err().to_string();
+        assert!(error.starts_with("1 compose conflicts"), "{}", error);
+        assert!(error.contains("account on mainnet") && error.contains("account on devnet (include.json)"), "{}", error);
+    }
+
+    #[test]
+    fn identical_definitions_do_not_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let pubkey = Pubkey::new_unique();
+        let mut merger = Merger::new(config(&[], &[(pubkey, "mainnet")]), &dir.path().join("root.json"), MergePolicy::Error);
+        merger.merge(&composed(dir.path(), "include.json", config(&[], &[(pubkey, "mainnet")])));
+
+        let (merged, report) = merger.finish().unwrap();
+        assert_eq!(merged.accounts.len(), 1);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn includes_configs_of_git_repositories() {
+        test_cache();
+        let (dir, repo) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let pubkey = Pubkey::new_unique();
+        write_config(repo.path(), "valid8.json", &config(&[], &[(pubkey, "mainnet")]));
+        fs::write(dir.path().join("outside.json"), b"{}").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("outside.json"), repo.path().join("escape.json")).unwrap();
+        git(repo.path(), &["init", "--quiet", "--initial-branch", "main"]);
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["commit", "--quiet", "-m", "fixtures"]);
+        let source = |path: &str| ComposeSource::Git { git: format!("file://{}", repo.path().display()), rev: "main".into(), path: path.into() };
+
+        let (resolved, lock) = resolve_source(dir.path(), source("valid8.json"), &Lockfile::default(), false).unwrap();
+        assert_eq!(resolved[0].config.accounts, vec![ConfigEntry::new(pubkey, Network::Mainnet)]);
+        assert!(lock.compose.values().all(|locked| locked.commit.is_some()));
+
+        for path in ["../outside.json", "/etc/hosts"] {
+            let error = resolve_source(dir.path(), source(path), &lock, false).unwrap_err().to_string();
+            assert!(error.contains("Invalid path"), "{}", error);
+        }
+        #[cfg(unix)]
+        {
+            let error = resolve_source(dir.path(), source("escape.json"), &lock, false).unwrap_err().to_string();
+            assert!(error.contains("outside of the repository"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn git_urls_are_not_read_as_options() {
+        test_cache();
+        // git clones the url as a repository instead of taking it as its --upload-pack option
+        let url = "--upload-pack=false";
+        let error = remote::git_checkout(url, "main", None, false).unwrap_err().to_string();
+        assert!(error.contains(&format!("'{}'", url)), "{}", error);
+        assert!(remote::git_checkout("file:///nowhere", "--output=x", None, false).unwrap_err().to_string().contains("Invalid revision"));
+    }
+
+    #[test]
+    fn pins_file_bundles_by_hash() {
+        test_cache();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.v8");
+        let write_fixtures = |pubkey: Pubkey| {
+            let mut fixtures = config(&[], &[(pubkey, "mainnet")]);
+            fixtures.project_name = ProjectName::from_str("fixtures").unwrap();
+            let config = serde_json::to_vec(&fixtures).unwrap();
+            let files: &[(&str, &[u8])] = &[("fixtures.json", &config)];
+            bundle::tests::write_bundle(&path, bundle::tests::manifest("fixtures", files), files);
+            storage::data_hash(&fs::read(&path).unwrap())
+        };
+        let source = ComposeSource::Bundle { bundle: format!("file://{}", path.display()) };
+        let id = format!("bundle+file://{}", path.display());
+
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let hash = write_fixtures(first);
+        let (resolved, lock) = resolve_source(dir.path(), source.clone(), &Lockfile::default(), false).unwrap();
+        assert_eq!(resolved[0].config.accounts, vec![ConfigEntry::new(first, Network::Mainnet)]);
+        assert_eq!(lock.compose.get(&id).and_then(|locked| locked.hash.clone()), Some(hash.clone()));
+        assert_eq!(resolve_source(dir.path(), source.clone(), &lock, false).unwrap().1, lock);
+
+        let changed_hash = write_fixtures(second);
+        let error = resolve_source(dir.path(), source.clone(), &lock, false).unwrap_err().to_string();
+        assert!(error.contains(&format!("locked hash {}", hash)), "{}", error);
+
+        let (resolved, updated) = resolve_source(dir.path(), source, &lock, true).unwrap();
+        assert_eq!(resolved[0].config.accounts, vec![ConfigEntry::new(second, Network::Mainnet)]);
+        assert_eq!(updated.compose.get(&id).and_then(|locked| locked.hash.clone()), Some(changed_hash));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+use anchor_lang::anchor_syn::idl::types::Idl;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::{bpf_loader_upgradeable::{self, UpgradeableLoaderState}, pubkey::Pubkey};
+
+use crate::{context::EditField, serialization::b58};
+
+use super::{idl::{self, DiscriminatorStrategy}, storage, AccountSchema, Network};
+
+/// A field name with its local and live value
+type Change = (String, Value, Value);
+
+/// How a local account compares to its live state
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    Unchanged,
+    Changed,
+    /// The account doesn't exist on the network anymore
+    Closed,
+}
+
+/// A field with a different local and live value, data fields are named like `data.orders[3].price` or `data[8..16]`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub local: Value,
+    pub live: Value,
+    /// The local value is set by an override of the config
+    pub overridden: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AccountDiff {
+    #[serde(with = "b58")]
+    pub pubkey: Pubkey,
+    pub network: Network,
+    /// The slot the live account was read at
+    pub slot: u64,
+    pub status: DiffStatus,
+    /// The IDL account type the data was compared as
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// The IDL of an account owner, used to compare data field by field
+pub struct OwnerIdl<'a> {
+    pub idl: &'a Idl,
+    pub raw_idl: &'a Value,
+    pub strategy: DiscriminatorStrategy,
+}
+
+/// Compares a local account, with its overrides applied, to its live state on `network`
+pub fn diff_account(local: &AccountSchema, live: Option<&AccountSchema>, network: &Network, slot: u64, owner_idl: Option<OwnerIdl>, overrides: &[&EditField]) -> AccountDiff {
+    let mut diff = AccountDiff { pubkey: local.pubkey, network: network.clone(), slot, status: DiffStatus::Closed, account_type: None, changes: vec![] };
+    let live = match live {
+        Some(live) => live,
+        None => return diff,
+    };
+
+    let mut changes = vec![];
+    if local.lamports != live.lamports {
+        changes.push(("lamports".to_string(), json!(local.lamports), json!(live.lamports)));
+    }
+    if local.owner != live.owner {
+        changes.push(("owner".to_string(), json!(local.owner.to_string()), json!(live.owner.to_string())));
+    }
+    if local.executable != live.executable {
+        changes.push(("executable".to_string(), json!(local.executable), json!(live.executable)));
+    }
+    if local.data.len() != live.data.len() {
+        changes.push(("data_len".to_string(), json!(local.data.len()), json!(live.data.len())));
+    }
+    if local.data != live.data {
+        if let Some(program_data_changes) = program_data_changes(local, live) {
+            changes.extend(program_data_changes);
+        } else if let Some((account_type, field_changes)) = owner_idl.and_then(|owner_idl| idl_changes(&owner_idl, &local.data, &live.data)) {
+            diff.account_type = Some(account_type);
+            changes.extend(field_changes);
+        } else {
+            changes.extend(byte_changes(&local.data, &live.data, 0));
+        }
+    }
+
+    diff.status = match changes.is_empty() {
+        true => DiffStatus::Unchanged,
+        false => DiffStatus::Changed,
+    };
+    diff.changes = changes
+        .into_iter()
+        .map(|(field, local, live)| {
+            let overridden = overrides.iter().any(|edit_field| overrides_field(edit_field, &field));
+            FieldChange { field, local, live, overridden }
+        })
+        .collect();
+    diff
+}
+
+fn overrides_field(edit_field: &EditField, field: &str) -> bool {
+    match edit_field {
+        EditField::Lamports(_) => field == "lamports",
+        EditField::Owner(_) => field == "owner",
+        EditField::UpgradeAuthority(_) => field == "program_data.upgrade_authority",
+        // Accounts are compared at their cloned address
+        EditField::Relocate { .. } => false,
+        // All other overrides edit the account data
+        _ => field.starts_with("data"),
+    }
+}
+
+fn program_data_changes(local: &AccountSchema, live: &AccountSchema) -> Option<Vec<Change>> {
+    // Program data is compared by its deployment and ELF hash, byte ranges of an ELF aren't useful
+    if local.owner != bpf_loader_upgradeable::id() || live.owner != bpf_loader_upgradeable::id() {
+        return None
+    }
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let read = |data: &[u8]| match bincode::deserialize(data.get(..metadata_len)?).ok()? {
+        UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address } => Some((slot, upgrade_authority_address, storage::data_hash(&data[metadata_len..]))),
+        _ => None,
+    };
+    let ((local_slot, local_authority, local_hash), (live_slot, live_authority, live_hash)) = (read(&local.data)?, read(&live.data)?);
+
+    let mut changes = vec![];
+    if local_slot != live_slot {
+        changes.push(("program_data.slot".to_string(), json!(local_slot), json!(live_slot)));
+    }
+    if local_authority != live_authority {
+        let authority = |authority: Option<Pubkey>| authority.map_or(Value::Null, |authority| json!(authority.to_string()));
+        changes.push(("program_data.upgrade_authority".to_string(), authority(local_authority), authority(live_authority)));
+    }
+    if local_hash != live_hash {
+        changes.push(("program_data.elf".to_string(), json!(local_hash), json!(live_hash)));
+    }
+    Some(changes)
+}
+
+fn idl_changes(owner_idl: &OwnerIdl, local: &[u8], live: &[u8]) -> Option<(String, Vec<Change>)> {
+    let decode = |data: &[u8]| idl::decode_idl_account_len(owner_idl.idl, owner_idl.raw_idl, owner_idl.strategy, data).ok().flatten();
+    let ((local_type, local_values, local_len), (live_type, live_values, live_len)) = (decode(local)?, decode(live)?);
+    // Accounts that changed their type are compared byte by byte
+    if local_type != live_type {
+        return None
+    }
+
+    let (mut local_fields, mut live_fields) = (BTreeMap::new(), BTreeMap::new());
+    flatten("data".to_string(), &local_values, &mut local_fields);
+    flatten("data".to_string(), &live_values, &mut live_fields);
+    let mut changes: Vec<Change> = local_fields
+        .keys()
+        .chain(live_fields.keys())
+        .collect::<BTreeSet<&String>>()
+        .into_iter()
+        .filter_map(|field| {
+            let (local, live) = (local_fields.get(field).unwrap_or(&Value::Null), live_fields.get(field).unwrap_or(&Value::Null));
+            (local != live).then(|| (field.clone(), local.clone(), live.clone()))
+        })
+        .collect();
+    // Bytes after the decoded fields, eg. space reserved for future fields
+    changes.extend(byte_changes(local, live, local_len.min(live_len)));
+    Some((local_type, changes))
+}
+
+fn flatten(path: String, value: &Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => map.iter().for_each(|(name, value)| flatten(format!("{}.{}", path, name), value, fields)),
+        // Arrays of structs are compared by element, arrays of numbers like `[u8; 32]` as a whole
+        Value::Array(values) if values.iter().any(|value| value.is_object() || value.is_array()) => {
+            values.iter().enumerate().for_each(|(index, value)| flatten(format!("{}[{}]", path, index), value, fields))
+        },
+        _ => { fields.insert(path, value.clone()); },
+    }
+}
+
+fn byte_changes(local: &[u8], live: &[u8], from: usize) -> Vec<Change> {
+    let hex = |data: &[u8], start: usize, end: usize| {
+        let bytes = data.get(start.min(data.len())..end.min(data.len())).unwrap_or_default();
+        json!(bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+    };
+    let len = local.len().max(live.len());
+    let mut changes = vec![];
+    let mut start = None;
+    for i in from..=len {
+        let changed = i < len && local.get(i) != live.get(i);
+        match (changed, start) {
+            (true, None) => start = Some(i),
+            (false, Some(range_start)) => {
+                changes.push((format!("data[{}..{}]", range_start, i), hex(local, range_start, i), hex(live, range_start, i)));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    changes
+}
@@ -1,18 +1,31 @@
-use std::{fs::File, io::{Read, Write}, path::Path, str::FromStr};
-use anyhow::{Error, Result};
-use flate2::read::ZlibDecoder;
-use anchor_lang::{idl::IdlAccount, AnchorDeserialize};
-use solana_sdk::pubkey::Pubkey;
+use std::{fs::{self, File}, io::{Read, Write}, path::Path, str::FromStr};
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use anchor_lang::{anchor_syn::idl::types::Idl, idl::IdlAccount, AnchorDeserialize};
+use serde_json::Value;
+use solana_sdk::{pubkey, pubkey::Pubkey};
 use crate::context::Valid8Context;
 
-use super::{AccountSchema, Network, project_name::ProjectName};
+use super::{idl::DiscriminatorStrategies, AccountSchema, Network, project_name::ProjectName, storage::{self, Storage}};
+
+pub const PROGRAM_METADATA_ID: Pubkey = pubkey!("ProgM6JCCvbYkfKqJYHePx4xxSUSqJp7rh8Lyv7nk7S");
+const PROGRAM_METADATA_HEADER_LEN: usize = 96;
+const PROGRAM_METADATA_IDL_SEED: &[u8] = b"idl";
 
 pub fn find_idl_address(pubkey: &Pubkey) -> Result<Pubkey> {
     Ok(IdlAccount::address(pubkey))
 }
 
-pub fn fetch_idl_schema(network: &Network, pubkey: &Pubkey) -> Result<Vec<u8>> {
-    let data = fetch_account_data(network, pubkey)?;
+pub fn find_program_metadata_idl_address(program_id: &Pubkey) -> Pubkey {
+    // Canonical metadata accounts are derived from the program and a 16 byte zero padded seed
+    let mut seed = [0u8; 16];
+    seed[..PROGRAM_METADATA_IDL_SEED.len()].copy_from_slice(PROGRAM_METADATA_IDL_SEED);
+    Pubkey::find_program_address(&[program_id.as_ref(), &seed], &PROGRAM_METADATA_ID).0
+}
+
+pub fn fetch_idl_schema(network: &Network, pubkey: &Pubkey, offline: bool) -> Result<Vec<u8>> {
+    let data = fetch_account_data(network, pubkey, offline)?;
     // Cut off account discriminator.
     let mut d: &[u8] = &data[8..];
     let idl_account: IdlAccount = AnchorDeserialize::deserialize(&mut d)?;
@@ -25,60 +38,138 @@ pub fn fetch_idl_schema(network: &Network, pubkey: &Pubkey) -> Result<Vec<u8>> {
     Ok(s.to_vec())
 }
 
-pub fn fetch_account(network: &Network, pubkey: &Pubkey) -> Result<AccountSchema> {
-    let client = network.client();
+pub fn fetch_program_metadata_idl(network: &Network, program_id: &Pubkey, offline: bool) -> Result<Vec<u8>> {
+    let data = fetch_account_data(network, &find_program_metadata_idl_address(program_id), offline)?;
+    if data.len() < PROGRAM_METADATA_HEADER_LEN {
+        return Err(anyhow!("Program metadata account too small"));
+    }
+
+    // Header: discriminator, program, authority, mutable, canonical, seed, then the data descriptors
+    let (encoding, compression, data_source) = (data[83], data[84], data[86]);
+    let data_len = u32::from_le_bytes(data[87..91].try_into()?) as usize;
+    if data_source != 0 {
+        return Err(anyhow!("Only IDLs stored directly in the program metadata account are supported"));
+    }
+    let raw = data
+        .get(PROGRAM_METADATA_HEADER_LEN..PROGRAM_METADATA_HEADER_LEN + data_len)
+        .ok_or(anyhow!("Program metadata data length out of bounds"))?;
+
+    let decoded = match encoding {
+        0 | 1 => raw.to_vec(),
+        2 => bs58::decode(raw).into_vec()?,
+        3 => base64::engine::general_purpose::STANDARD.decode(raw)?,
+        e => return Err(anyhow!("Unknown program metadata encoding: {}", e)),
+    };
+
+    let mut s = Vec::new();
+    match compression {
+        0 => s = decoded,
+        1 => { GzDecoder::new(decoded.as_slice()).read_to_end(&mut s)?; },
+        2 => { ZlibDecoder::new(decoded.as_slice()).read_to_end(&mut s)?; },
+        c => return Err(anyhow!("Unknown program metadata compression: {}", c)),
+    }
+    Ok(s)
+}
+
+pub fn read_local_idl(path: &str) -> Result<Vec<u8>> {
+    let data = fs::read(Path::new(path))?;
+    // Only accept files we are able to load later on
+    serde_json::from_slice::<Idl>(&data).map_err(|e| anyhow!("Invalid IDL file {}: {}", path, e))?;
+    Ok(data)
+}
+
+pub fn find_workspace_idl(project_name: &ProjectName, program_id: &Pubkey) -> Result<Vec<u8>> {
+    // Anchor workspaces write their IDLs to target/idl next to the config, with the program id under metadata.address
+    let config = project_name.to_config();
+    let workspace = Path::new(&config).parent().unwrap_or(Path::new(""));
+    for entry in fs::read_dir(workspace.join("target").join("idl"))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let data = fs::read(&path)?;
+        if let Ok(idl) = serde_json::from_slice::<Value>(&data) {
+            if idl["metadata"]["address"].as_str() == Some(&program_id.to_string()) {
+                return Ok(data)
+            }
+        }
+    }
+    Err(anyhow!("No IDL for {} in Anchor workspace", program_id))
+}
+
+pub fn fetch_account(network: &Network, pubkey: &Pubkey, offline: bool) -> Result<AccountSchema> {
+    let client = network.client(offline)?;
     let account_scema = AccountSchema::from_account( &client.get_account(pubkey)?, pubkey, network)?;
     Ok(account_scema)
 }
 
-pub fn fetch_account_data(network: &Network, pubkey: &Pubkey) -> Result<Vec<u8>> {
-    let client = network.client();
+/// Fetches an account with the slot it was read at
+pub fn fetch_account_with_slot(network: &Network, pubkey: &Pubkey, offline: bool) -> Result<(AccountSchema, u64)> {
+    let (account, slot) = fetch_live_account(network, pubkey, offline)?;
+    let account = account.ok_or(anyhow!("Account {} not found on {}", pubkey, network))?;
+    Ok((account, slot))
+}
+
+/// Fetches an account that may not exist, with the slot it was read at
+pub fn fetch_live_account(network: &Network, pubkey: &Pubkey, offline: bool) -> Result<(Option<AccountSchema>, u64)> {
+    let client = network.client(offline)?;
+    let response = client.get_account_with_commitment(pubkey, client.commitment())?;
+    let account = response.value.map(|account| AccountSchema::from_account(&account, pubkey, network)).transpose()?;
+    Ok((account, response.context.slot))
+}
+
+pub fn fetch_account_data(network: &Network, pubkey: &Pubkey, offline: bool) -> Result<Vec<u8>> {
+    let client = network.client(offline)?;
     Ok(client.get_account_data(pubkey)?)
 }
 
 pub fn clone_program_data(ctx: &Valid8Context, account: &AccountSchema) -> Result<AccountSchema> {
     // Get program executable data address
     let program_executable_data_address = account.get_program_executable_data_address()?;
-    let program_executable_data_account = fetch_account(&account.network, &program_executable_data_address)?;
+    let program_executable_data_account = fetch_account(&account.network, &program_executable_data_address, ctx.offline)?;
     // ctx.add_account(network, &program_executable_data_address)?;
 
     // Fetch program executable data
-    let program_executable_data = fetch_account_data(&account.get_network(), &program_executable_data_address)?;
+    let program_executable_data = fetch_account_data(&account.get_network(), &program_executable_data_address, ctx.offline)?;
 
-    // Save program executable data
-    save_program(&ctx.project_name, &account.get_pubkey(), &program_executable_data)?;
+    // Save program executable data, the cache already has it in the program data account
+    if ctx.storage() != Storage::Cache {
+        save_program(&ctx.project_name, &account.get_pubkey(), &program_executable_data)?;
+    }
     Ok(program_executable_data_account)
 }
 
-pub fn clone_idl(program_account: &AccountSchema) -> Result<()> {
-    // Get IDL address
-    let idl_address = find_idl_address(&program_account.pubkey)?;
+pub fn clone_idl(ctx: &Valid8Context, program_account: &AccountSchema) -> Result<()> {
+    let program_id = &program_account.pubkey;
 
-    // Get IDL data
-    match fetch_idl_schema(&program_account.network, &idl_address) {
-        Ok(d) => {
-            save_idl(&ProjectName::default(), &program_account.pubkey, &d)
-        },
-        Err(e) => {
-            Err(Error::msg(e.to_string()))
-        }
+    // A registered local IDL is used as is, its errors aren't hidden by the other sources
+    if let Some(path) = ctx.local_idls.as_ref().and_then(|local_idls| local_idls.get(&program_id.to_string())) {
+        let idl = read_local_idl(path).map_err(|e| anyhow!("Local IDL of {}: {}", program_id, e))?;
+        return save_idl(&ctx.project_name, program_id, &idl)
     }
+
+    // Otherwise try the Anchor workspace, then both on-chain locations
+    let idl = find_workspace_idl(&ctx.project_name, program_id)
+        .or_else(|_| fetch_idl_schema(&program_account.network, &find_idl_address(program_id)?, ctx.offline))
+        .or_else(|_| fetch_program_metadata_idl(&program_account.network, program_id, ctx.offline))
+        .map_err(|_| anyhow!("No IDL found for {}", program_id))?;
+
+    save_idl(&ctx.project_name, program_id, &idl)
+}
+
+pub fn save_account_to_disc(project_name: &ProjectName, storage: Storage, discriminators: Option<&DiscriminatorStrategies>, account_schema: &AccountSchema) -> Result<String> {
+    Ok(storage::save_account(project_name, storage, discriminators, account_schema)?.display().to_string())
 }
 
-pub fn save_account_to_disc(project_name: &ProjectName, account_schema: &AccountSchema) -> Result<String> {
-    let account_bytes = bincode::serialize(account_schema)?;
-    File::create(Path::new(&format!("{}{}.bin", project_name.to_resources(), account_schema.pubkey)))
-        .and_then(|mut file| file.write_all(&account_bytes))?;
-    Ok(format!("{}{}", project_name.to_resources(), account_schema.pubkey))
+/// Saves an account that was cloned, imported or created, with its pristine copy
+pub fn save_cloned_account(project_name: &ProjectName, storage: Storage, discriminators: Option<&DiscriminatorStrategies>, account_schema: &AccountSchema) -> Result<String> {
+    storage::save_pristine(project_name, account_schema)?;
+    save_account_to_disc(project_name, storage, discriminators, account_schema)
 }
 
 pub fn read_account_from_disc(project_name: &ProjectName, pubkey_str: &str) -> Result<AccountSchema> {
-    let pubkey = Pubkey::from_str(pubkey_str)?;
-    let mut account_bytes = vec![];
-    File::open(Path::new(&format!("{}{}.bin", project_name.to_resources(), pubkey)))
-        .and_then(|mut file| file.read_to_end(&mut account_bytes))?;
-    let account = bincode::deserialize(&account_bytes)?;
-    Ok(account)
+    // The storage format is detected from the files on disc
+    storage::read_account(project_name, &Pubkey::from_str(pubkey_str)?)
 }
 
 pub fn save_idl(project_name: &ProjectName, pubkey: &Pubkey, data: &[u8]) -> Result<()> {
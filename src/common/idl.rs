@@ -1,41 +1,88 @@
-use std::{collections::HashMap, fs::File, io::Read, mem, path::Path, str::FromStr};
+use std::{collections::{BTreeMap, HashMap}, fs::File, io::Read, path::Path, str::FromStr};
 use anyhow::{anyhow, Ok, Result};
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshSerialize;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde_json::{json, Value};
 use solana_sdk::{pubkey::Pubkey, hash::hash};
-use convert_case::{Case, Casing};
 
-use anchor_lang::anchor_syn::idl::types::{Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy::Struct};
+use anchor_lang::anchor_syn::idl::types::{EnumFields, Idl, IdlField, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy::{Alias, Enum, Struct}};
+
+use super::project_name::ProjectName;
 
 
 pub type Discriminator = [u8;8];
 pub type DiscriminatorMap = HashMap<[u8;8], IdlTypeDefinition>;
+/// Discriminator strategies set in the config, by program id
+pub type DiscriminatorStrategies = BTreeMap<String, DiscriminatorStrategy>;
+
+/// How the account type of an account owned by a program is identified
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscriminatorStrategy {
+    /// 8 byte `account:<Name>` sighash prefix, used by Anchor
+    #[default]
+    Anchor,
+    /// Single byte `key` enum field, used by Shank programs like Metaplex Token Metadata
+    Key,
+    /// No discriminator, the account type is selected explicitly
+    None,
+}
+
+impl DiscriminatorStrategy {
+    pub fn detect(idl: &Idl) -> Self {
+        match idl.metadata.as_ref().and_then(|m| m["origin"].as_str()) {
+            Some("shank") => DiscriminatorStrategy::Key,
+            _ => DiscriminatorStrategy::Anchor,
+        }
+    }
+
+    /// The strategy configured for a program, or the one detected from its IDL
+    pub fn configured(discriminators: Option<&DiscriminatorStrategies>, program_id: &Pubkey, idl: &Idl) -> Self {
+        discriminators
+            .and_then(|discriminators| discriminators.get(&program_id.to_string()).copied())
+            .unwrap_or_else(|| DiscriminatorStrategy::detect(idl))
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IdlAccountField {
     pub name: String,
     pub value: Option<FieldValue>,
     pub field_len: usize,
+    pub offset: usize,
     pub orig_idl_field: IdlField
 }
 
 impl IdlAccountField {
     pub fn edit(&mut self, new_value: String) -> Result<()> {
-        self.value = match &self.orig_idl_field.ty {
-            IdlType::Bool | IdlType::U8 | IdlType::I8 | IdlType::U16 | IdlType::I16 | IdlType::U32 | IdlType::I32 | IdlType::U64 | IdlType::I64 | 
-            IdlType::U128 | IdlType::I128 | IdlType::U256 | IdlType::I256 => Some(FieldValue::Number(new_value.parse::<usize>()?)),
-            IdlType::F32 => unimplemented!(),
-            IdlType::F64 => unimplemented!(),
-            IdlType::Bytes => unimplemented!(),
+        // Integers are parsed in the width of the field, so out of range values are rejected with the type
+        let ty = &self.orig_idl_field.ty;
+        let invalid = |e: &dyn std::fmt::Display| anyhow!("Invalid value {} for {}, field type: {:?}: {}", new_value, self.name, ty, e);
+        macro_rules! int {
+            ($variant:ident, $ty:ty) => {
+                FieldValue::$variant(new_value.parse::<$ty>().map_err(|e| invalid(&e))?.into())
+            };
+        }
+        self.value = match ty {
+            IdlType::Bool => Some(FieldValue::Number(match new_value.as_str() {
+                "0" | "false" => 0,
+                "1" | "true" => 1,
+                _ => return Err(invalid(&"expected 0, 1, true or false")),
+            })),
+            IdlType::U8 => Some(int!(Number, u8)),
+            IdlType::I8 => Some(int!(Signed, i8)),
+            IdlType::U16 => Some(int!(Number, u16)),
+            IdlType::I16 => Some(int!(Signed, i16)),
+            IdlType::U32 => Some(int!(Number, u32)),
+            IdlType::I32 => Some(int!(Signed, i32)),
+            IdlType::U64 => Some(int!(Number, u64)),
+            IdlType::I64 => Some(int!(Signed, i64)),
+            IdlType::U128 => Some(int!(Number, u128)),
+            IdlType::I128 => Some(int!(Signed, i128)),
+            IdlType::F32 | IdlType::F64 => Some(FieldValue::Float(new_value.parse::<f64>()?)),
             IdlType::String => Some(FieldValue::String(new_value)),
             IdlType::PublicKey => Some(FieldValue::Pubkey(Pubkey::from_str(&new_value)?)),
-            IdlType::Defined(_) => unimplemented!(),
-            IdlType::Option(_) => unimplemented!(),
-            IdlType::Vec(_) => unimplemented!(),
-            IdlType::Array(_, _) => unimplemented!(),
-            IdlType::GenericLenArray(_, _) => unimplemented!(),
-            IdlType::Generic(_) => unimplemented!(),
-            IdlType::DefinedWithTypeArgs { name: _name, args: _args } => todo!(),
+            _ => return Err(anyhow!("Editing {} is not supported, field type: {:?}", self.name, self.orig_idl_field.ty)),
         };
         Ok(())
     }
@@ -43,61 +90,45 @@ impl IdlAccountField {
 
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
         let mut buf = vec![];
-        match &self.orig_idl_field.ty {
-            IdlType::Bool | IdlType::U8 => {
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    u8::serialize(&u8::try_from(*num)?, &mut buf)?;
+        // Integers are kept widened, they're checked against the range of the field type here
+        macro_rules! int {
+            ($variant:ident, $ty:ty) => {
+                if let Some(FieldValue::$variant(num)) = &self.value {
+                    let num = <$ty>::try_from(*num).map_err(|_| anyhow!("{} is out of range for {}, field type: {:?}", num, self.name, self.orig_idl_field.ty))?;
+                    <$ty>::serialize(&num, &mut buf)?;
                 }
-            },
-            IdlType::I8 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    i8::serialize(&i8::try_from(*num)?, &mut buf)?;
-                }
-            }
-            IdlType::U16 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    u16::serialize(&u16::try_from(*num)?, &mut buf)?;
-                }
-            },
-            IdlType::I16 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    i16::serialize(&i16::try_from(*num)?, &mut buf)?;
-                }
-            },
-            IdlType::U32 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    u32::serialize(&u32::try_from(*num)?, &mut buf)?;
-                }
-            },
-            IdlType::I32 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    i32::serialize(&i32::try_from(*num)?, &mut buf)?;
-                }
-            },
-            IdlType::F32 => unimplemented!(),
-            IdlType::U64 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    u64::serialize(&u64::try_from(*num)?, &mut buf)?;
-                }
-            },
-            IdlType::I64 => {              
+            };
+        }
+
+        match &self.orig_idl_field.ty {
+            IdlType::Bool => {
                 if let Some(FieldValue::Number(num)) = &self.value {
-                    i64::serialize(&i64::try_from(*num)?, &mut buf)?;
+                    match num {
+                        0 | 1 => buf.push(*num as u8),
+                        _ => return Err(anyhow!("{} is not a bool, expected 0 or 1", num)),
+                    }
                 }
             },
-            IdlType::F64 => unimplemented!(),
-            IdlType::U128 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    u128::serialize(&u128::try_from(*num)?, &mut buf)?;
+            IdlType::U8 => int!(Number, u8),
+            IdlType::I8 => int!(Signed, i8),
+            IdlType::U16 => int!(Number, u16),
+            IdlType::I16 => int!(Signed, i16),
+            IdlType::U32 => int!(Number, u32),
+            IdlType::I32 => int!(Signed, i32),
+            IdlType::F32 => {
+                if let Some(FieldValue::Float(num)) = &self.value {
+                    buf.extend_from_slice(&(*num as f32).to_le_bytes());
                 }
             },
-            IdlType::I128 => {              
-                if let Some(FieldValue::Number(num)) = &self.value {
-                    i128::serialize(&i128::try_from(*num)?, &mut buf)?;
+            IdlType::F64 => {
+                if let Some(FieldValue::Float(num)) = &self.value {
+                    buf.extend_from_slice(&num.to_le_bytes());
                 }
             },
-            IdlType::U256 => unimplemented!(),
-            IdlType::I256 => unimplemented!(),
+            IdlType::U64 => int!(Number, u64),
+            IdlType::I64 => int!(Signed, i64),
+            IdlType::U128 => int!(Number, u128),
+            IdlType::I128 => int!(Signed, i128),
             IdlType::Bytes => {              
                 if let Some(FieldValue::Bytes(bytes)) = &self.value {
                     buf = bytes.clone();
@@ -113,9 +144,12 @@ impl IdlAccountField {
                     Pubkey::serialize(pubkey, &mut buf)?;
                 }
             },
-            IdlType::Defined(_) => unimplemented!(),
-            IdlType::Option(_) => unimplemented!(),
-            IdlType::Vec(_) => unimplemented!(),
+            IdlType::Defined(_) | IdlType::Option(_) | IdlType::Vec(_) => {
+                // Complex values are kept as their raw borsh bytes
+                if let Some(FieldValue::Bytes(bytes)) = &self.value {
+                    buf = bytes.clone();
+                }
+            },
             IdlType::Array(idl_type, _length) => {
                 if let Some(FieldValue::Array(field_values)) = &self.value {
                     let _ = field_values.iter().map(|fv| {
@@ -123,6 +157,7 @@ impl IdlAccountField {
                             name: "".into(),
                             value: Some(fv.clone()),
                             field_len: 0,
+                            offset: 0,
                             orig_idl_field: IdlField { name: "".into(), docs: None, ty: *idl_type.clone() },
                         };
 
@@ -131,140 +166,772 @@ impl IdlAccountField {
                     }).collect::<Result<Vec<()>>>()?;
                 }
             },
-            IdlType::GenericLenArray(_, _) => unimplemented!(),
-            IdlType::Generic(_) => unimplemented!(),
-            IdlType::DefinedWithTypeArgs { name: _, args: _ } => unimplemented!(),
+            _ => return Err(anyhow!("Serializing {} is not supported, field type: {:?}", self.name, self.orig_idl_field.ty)),
         }
         Ok(buf)
 
     }
 }
 
-pub fn unpack_data_idl_type(idl_type: &IdlType, data: &[u8]) -> Result<FieldValue> {
-    match idl_type {
-        IdlType::Bool => Ok(FieldValue::Number(u8::try_from_slice(data)? as usize)),
-        IdlType::U8 => Ok(FieldValue::Number(u8::try_from_slice(data)?as usize)),
-        IdlType::I8 => Ok(FieldValue::Number(i8::try_from_slice(data)? as usize)),
-        IdlType::U16 => Ok(FieldValue::Number(u16::try_from_slice(data)? as usize)),
-        IdlType::I16 => Ok(FieldValue::Number(i16::try_from_slice(data)? as usize)),
-        IdlType::U32 => Ok(FieldValue::Number(u32::try_from_slice(data)? as usize)),
-        IdlType::I32 => Ok(FieldValue::Number(i32::try_from_slice(data)? as usize)),
-        IdlType::F32 => Ok(FieldValue::Number(f32::try_from_slice(data)? as usize)),
-        IdlType::U64 => Ok(FieldValue::Number(u64::try_from_slice(data)? as usize)),
-        IdlType::I64 => Ok(FieldValue::Number(i64::try_from_slice(data)? as usize)),
-        IdlType::F64 => Ok(FieldValue::Number(f64::try_from_slice(data)? as usize)),
-        IdlType::U128 => Ok(FieldValue::Number(u128::try_from_slice(data)? as usize)),
-        IdlType::I128 => Ok(FieldValue::Number(i128::try_from_slice(data)? as usize)),
-        IdlType::U256 => todo!(),
-        IdlType::I256 => todo!(),
-        IdlType::Bytes => Ok(FieldValue::Bytes(data.to_vec())),
-        IdlType::String => Ok(FieldValue::String(String::from_utf8(data.to_vec())?)),
-        IdlType::PublicKey => Ok(FieldValue::Pubkey(Pubkey::try_from_slice(data)?)),
-        IdlType::Defined(_) => todo!(),
-        IdlType::Option(_) => todo!(),
-        IdlType::Vec(_) => todo!(),
-        IdlType::Array(item_type, length) => {
-            let idl_type_len = idl_type_len(item_type);
-            let mut offset = 0usize;
-            let collection = (0..*length).map(|_| {
-                let item = unpack_data_idl_type(item_type, &data[offset..offset+idl_type_len])?;
-                offset += idl_type_len;
-                Ok(item)
-            }).collect::<Result<Vec<FieldValue>>>()?;
-
-            Ok(FieldValue::Array(collection))
-        },
-        IdlType::GenericLenArray(_, _) => todo!(),
-        IdlType::Generic(_) => todo!(),
-        IdlType::DefinedWithTypeArgs { name: _, args: _ } => todo!(),
-    }
+/// Decodes a value at the start of data to a field value, with the same decoder as `decode_idl_account`
+pub fn unpack_data_idl_type(idl: &Idl, idl_type: &IdlType, data: &[u8]) -> Result<FieldValue> {
+    let mut rest = data;
+    FieldValue::from_json(idl, idl_type, &unpack_idl_value(idl, idl_type, &mut rest)?)
 }
 
 #[derive(Debug, Clone)]
 pub enum FieldValue {
     String(String),
-    Number(usize),
+    /// Unsigned integers and bools
+    Number(u128),
+    Signed(i128),
     Bytes(Vec<u8>),
+    Float(f64),
     Pubkey(Pubkey),
     Array(Vec<FieldValue>)
 }
 
-pub fn open_idl(pubkey: &Pubkey) -> Result<Idl> {
+impl FieldValue {
+    /// Converts a decoded json value, options, vectors, bytes and structs keep their borsh bytes
+    pub fn from_json(idl: &Idl, idl_type: &IdlType, value: &Value) -> Result<FieldValue> {
+        let invalid = || anyhow!("Invalid {:?} value: {}", idl_type, value);
+        Ok(match idl_type {
+            IdlType::Bool => FieldValue::Number(value.as_bool().ok_or_else(invalid)?.into()),
+            IdlType::U8 | IdlType::U16 | IdlType::U32 | IdlType::U64 => FieldValue::Number(value.as_u64().ok_or_else(invalid)?.into()),
+            IdlType::I8 | IdlType::I16 | IdlType::I32 | IdlType::I64 => FieldValue::Signed(value.as_i64().ok_or_else(invalid)?.into()),
+            // Json numbers are limited to 64 bits, 128 bit integers are decoded as strings
+            IdlType::U128 => FieldValue::Number(value.as_str().ok_or_else(invalid)?.parse()?),
+            IdlType::I128 => FieldValue::Signed(value.as_str().ok_or_else(invalid)?.parse()?),
+            IdlType::F32 | IdlType::F64 => FieldValue::Float(value.as_f64().ok_or_else(invalid)?),
+            IdlType::String => FieldValue::String(value.as_str().ok_or_else(invalid)?.to_string()),
+            IdlType::PublicKey => FieldValue::Pubkey(Pubkey::from_str(value.as_str().ok_or_else(invalid)?)?),
+            IdlType::Array(item_type, _) => FieldValue::Array(
+                value.as_array().ok_or_else(invalid)?.iter().map(|item| FieldValue::from_json(idl, item_type, item)).collect::<Result<Vec<FieldValue>>>()?
+            ),
+            _ => {
+                let mut buf = vec![];
+                pack_idl_value(idl, idl_type, value, &mut buf)?;
+                FieldValue::Bytes(buf)
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::String(value) => write!(f, "{}", value),
+            FieldValue::Number(value) => write!(f, "{}", value),
+            FieldValue::Signed(value) => write!(f, "{}", value),
+            FieldValue::Float(value) => write!(f, "{}", value),
+            FieldValue::Bytes(bytes) => write!(f, "{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            FieldValue::Pubkey(pubkey) => write!(f, "{}", pubkey),
+            FieldValue::Array(values) => write!(f, "[{}]", values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", ")),
+        }
+    }
+}
+
+pub fn open_idl(project_name: &ProjectName, pubkey: &Pubkey) -> Result<Idl> {
     let mut b: Vec<u8> = vec![];
-    let mut f = File::open(Path::new(&format!("./.valid8/{}.idl.json", pubkey.to_string())))?;
+    let mut f = File::open(Path::new(&format!("{}{}.idl.json", project_name.to_resources(), pubkey)))?;
     f.read_to_end(&mut b)?;
     let schema: Idl = serde_json::from_slice(&b)?;
     Ok(schema)
 }
 
+pub fn open_idl_json(project_name: &ProjectName, pubkey: &Pubkey) -> Result<Value> {
+    let mut b: Vec<u8> = vec![];
+    let mut f = File::open(Path::new(&format!("{}{}.idl.json", project_name.to_resources(), pubkey)))?;
+    f.read_to_end(&mut b)?;
+    Ok(serde_json::from_slice(&b)?)
+}
+
+/// How an IDL type is laid out in account data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Serialization {
+    Borsh,
+    /// `#[account(zero_copy)]` types, C layout with padding, or without it when packed
+    ZeroCopy { packed: bool },
+}
+
+pub fn type_serialization(raw_idl: &Value, name: &str) -> Serialization {
+    // The parsed IDL drops the serialization and repr metadata, so read them from the raw json
+    let type_json = ["accounts", "types"]
+        .iter()
+        .filter_map(|key| raw_idl[key].as_array())
+        .flatten()
+        .find(|t| t["name"].as_str() == Some(name));
+
+    match type_json {
+        Some(t) if matches!(t["serialization"].as_str(), Some("bytemuck") | Some("bytemuckunsafe")) => {
+            Serialization::ZeroCopy { packed: t["repr"]["packed"].as_bool().unwrap_or(false) }
+        },
+        _ => Serialization::Borsh,
+    }
+}
+
+pub fn unpack_account(idl: &Idl, raw_idl: &Value, idl_type_def: &IdlTypeDefinition, data: &[u8]) -> Result<Vec<IdlAccountField>> {
+    match type_serialization(raw_idl, &idl_type_def.name) {
+        Serialization::Borsh => unpack_idl_account(idl, idl_type_def, data),
+        Serialization::ZeroCopy { packed } => {
+            let fields = match &idl_type_def.ty {
+                Struct { fields } => fields,
+                _ => return Err(anyhow!("Unsupported zero copy IDL type: {:?}", idl_type_def.ty)),
+            };
+            let mut account_map = vec![];
+            unpack_zero_copy_fields(idl, raw_idl, "", fields, packed, 0, data, &mut account_map)?;
+            Ok(account_map)
+        },
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn unpack_zero_copy_fields(idl: &Idl, raw_idl: &Value, prefix: &str, fields: &[IdlField], packed: bool, base: usize, data: &[u8], account_map: &mut Vec<IdlAccountField>) -> Result<()> {
+    let (offsets, _, _) = zero_copy_struct_layout(idl, raw_idl, fields, packed)?;
+
+    for (field, offset) in fields.iter().zip(offsets) {
+        let name = format!("{}{}", prefix, field.name);
+        let offset = base + offset;
+
+        // Nested structs and arrays of structs are flattened, eg. `bids[3].price`
+        match &field.ty {
+            IdlType::Defined(defined) => if let Some((nested, nested_packed)) = zero_copy_struct(idl, raw_idl, defined) {
+                unpack_zero_copy_fields(idl, raw_idl, &format!("{}.", name), nested, nested_packed, offset, data, account_map)?;
+                continue;
+            },
+            IdlType::Array(item, length) => if let IdlType::Defined(defined) = item.as_ref() {
+                if let Some((nested, nested_packed)) = zero_copy_struct(idl, raw_idl, defined) {
+                    let (item_size, _) = zero_copy_layout(idl, raw_idl, item, packed)?;
+                    for index in 0..*length {
+                        let item_prefix = format!("{}[{}].", name, index);
+                        unpack_zero_copy_fields(idl, raw_idl, &item_prefix, nested, nested_packed, offset + index * item_size, data, account_map)?;
+                    }
+                    continue;
+                }
+            },
+            _ => {},
+        }
+
+        let ty = resolve_idl_type(idl, &field.ty);
+        let (field_len, _) = zero_copy_layout(idl, raw_idl, &ty, packed)?;
+        let data_slice = data.get(offset..offset + field_len).ok_or(anyhow!("Account data too short for {}", name))?;
+        account_map.push(IdlAccountField {
+            value: Some(unpack_data_idl_type(idl, &ty, data_slice)?),
+            field_len,
+            offset,
+            orig_idl_field: IdlField { name: name.clone(), docs: None, ty },
+            name,
+        });
+    }
+    Ok(())
+}
+
+fn zero_copy_struct<'a>(idl: &'a Idl, raw_idl: &Value, name: &str) -> Option<(&'a Vec<IdlField>, bool)> {
+    match find_defined_type(idl, name) {
+        Some(IdlTypeDefinition { ty: Struct { fields }, .. }) => {
+            let packed = matches!(type_serialization(raw_idl, name), Serialization::ZeroCopy { packed: true });
+            Some((fields, packed))
+        },
+        _ => None,
+    }
+}
+
+fn zero_copy_struct_layout(idl: &Idl, raw_idl: &Value, fields: &[IdlField], packed: bool) -> Result<(Vec<usize>, usize, usize)> {
+    // C layout: every field starts at a multiple of its alignment, the struct size is a multiple of the largest one
+    let mut offsets = vec![];
+    let (mut size, mut align) = (0usize, 1usize);
+    for field in fields {
+        let (field_size, field_align) = zero_copy_layout(idl, raw_idl, &field.ty, packed)?;
+        size = size.next_multiple_of(field_align);
+        offsets.push(size);
+        size += field_size;
+        align = align.max(field_align);
+    }
+    Ok((offsets, size.next_multiple_of(align), align))
+}
+
+pub fn zero_copy_layout(idl: &Idl, raw_idl: &Value, idl_type: &IdlType, packed: bool) -> Result<(usize, usize)> {
+    let (size, align) = match idl_type {
+        IdlType::Bool | IdlType::U8 | IdlType::I8 => (1, 1),
+        IdlType::U16 | IdlType::I16 => (2, 2),
+        IdlType::U32 | IdlType::I32 | IdlType::F32 => (4, 4),
+        IdlType::U64 | IdlType::I64 | IdlType::F64 => (8, 8),
+        // 128 bit integers are 8 byte aligned on the SBF target
+        IdlType::U128 | IdlType::I128 => (16, 8),
+        IdlType::PublicKey => (32, 1),
+        IdlType::Array(item, length) => {
+            let (item_size, item_align) = zero_copy_layout(idl, raw_idl, item, packed)?;
+            (item_size * length, item_align)
+        },
+        IdlType::Defined(name) => match zero_copy_struct(idl, raw_idl, name) {
+            Some((fields, nested_packed)) => {
+                let (_, size, align) = zero_copy_struct_layout(idl, raw_idl, fields, nested_packed)?;
+                (size, align)
+            },
+            None => match resolve_idl_type(idl, idl_type) {
+                IdlType::Defined(_) => return Err(anyhow!("Unsupported zero copy type: {}", name)),
+                resolved => zero_copy_layout(idl, raw_idl, &resolved, packed)?,
+            },
+        },
+        _ => return Err(anyhow!("Unsupported zero copy type: {:?}", idl_type)),
+    };
+    Ok(if packed { (size, 1) } else { (size, align) })
+}
+
+pub fn account_discriminator(name: &str) -> Discriminator {
+    let mut discriminator: Discriminator = [0u8;8];
+    discriminator[0..8].copy_from_slice(&hash(format!("account:{}", name).as_bytes()).to_bytes()[0..8]);
+    discriminator
+}
+
 pub fn generate_discriminator_map(idl: &Idl) -> Result<DiscriminatorMap> {
     let map: DiscriminatorMap = idl.accounts.par_iter().map(|a| {
-        let mut discriminator: Discriminator = [0u8;8];
-        discriminator[0..8].copy_from_slice(&hash(format!("account:{}", a.name).as_bytes()).to_bytes()[0..8]);
-        (discriminator, a.clone())
+        (account_discriminator(&a.name), a.clone())
     }).collect();
 
     Ok(map)
 }
 
-pub fn unpack_idl_account(idl_type_def: &IdlTypeDefinition, data: Vec<u8>) -> Result<Vec<IdlAccountField>> {
-    let mut account_map: Vec<IdlAccountField> = vec!();
-    match idl_type_def.ty.clone() {
+pub fn pack_idl_account(idl: &Idl, raw_idl: &Value, idl_type_def: &IdlTypeDefinition, strategy: DiscriminatorStrategy, value: &Value) -> Result<Vec<u8>> {
+    // Only Anchor accounts carry a separate discriminator, Shank keys are part of the values
+    let mut buf = match strategy {
+        DiscriminatorStrategy::Anchor => account_discriminator(&idl_type_def.name).to_vec(),
+        DiscriminatorStrategy::Key | DiscriminatorStrategy::None => vec![],
+    };
+
+    match type_serialization(raw_idl, &idl_type_def.name) {
+        Serialization::Borsh => pack_idl_type_def(idl, idl_type_def, value, &mut buf)?,
+        Serialization::ZeroCopy { packed } => {
+            let fields = match &idl_type_def.ty {
+                Struct { fields } => fields,
+                _ => return Err(anyhow!("Unsupported zero copy IDL type: {:?}", idl_type_def.ty)),
+            };
+            let (_, size, _) = zero_copy_struct_layout(idl, raw_idl, fields, packed)?;
+            let mut data = vec![0u8; size];
+
+            // Write every flattened field at its offset, padding stays zeroed
+            let mut layout = vec![];
+            unpack_zero_copy_fields(idl, raw_idl, "", fields, packed, 0, &data.clone(), &mut layout)?;
+            for field in layout {
+                let field_value = json_path(value, &field.name).ok_or(anyhow!("Missing field {}.{}", idl_type_def.name, field.name))?;
+                let mut field_buf = vec![];
+                pack_idl_value(idl, &field.orig_idl_field.ty, field_value, &mut field_buf)?;
+                data[field.offset..field.offset + field.field_len].copy_from_slice(&field_buf);
+            }
+            buf.extend_from_slice(&data);
+        },
+    }
+    Ok(buf)
+}
+
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    // Follows flattened field names like `bids[3].price`
+    path.split('.').try_fold(value, |value, segment| {
+        let mut parts = segment.split('[');
+        let value = value.get(parts.next()?)?;
+        parts.try_fold(value, |value, index| value.get(index.trim_end_matches(']').parse::<usize>().ok()?))
+    })
+}
+
+fn pack_idl_type_def(idl: &Idl, idl_type_def: &IdlTypeDefinition, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+    match &idl_type_def.ty {
         Struct { fields } => {
-            let mut offset = 0usize;
             for field in fields {
-                let mut idl_field = unpack_idl_field(field);
-                if let Some(data_slice) = &data.get(offset..offset + idl_field.field_len) {
-                    idl_field.value = Some(unpack_data_idl_type(&idl_field.orig_idl_field.ty, data_slice)?);
-                    offset += &idl_field.field_len;
-                    account_map.push(idl_field);
-                }
+                let field_value = value.get(&field.name).ok_or(anyhow!("Missing field {}.{}", idl_type_def.name, field.name))?;
+                pack_idl_value(idl, &field.ty, field_value, buf)?;
+            }
+        },
+        Alias { value: alias } => pack_idl_value(idl, alias, value, buf)?,
+        Enum { variants } => {
+            // Enums are given as "Variant", or {"Variant": fields} for variants with fields
+            let (name, fields_value) = match value {
+                Value::String(name) => (name.as_str(), &Value::Null),
+                Value::Object(map) if map.len() == 1 => map.iter().next().map(|(k, v)| (k.as_str(), v)).unwrap(),
+                _ => return Err(anyhow!("Invalid value for enum {}: {}", idl_type_def.name, value)),
             };
+            let index = variants.iter().position(|v| v.name == name).ok_or(anyhow!("Unknown variant {} for enum {}", name, idl_type_def.name))?;
+            buf.push(u8::try_from(index)?);
+            match &variants[index].fields {
+                Some(EnumFields::Named(fields)) => for field in fields {
+                    let field_value = fields_value.get(&field.name).ok_or(anyhow!("Missing field {}.{}", name, field.name))?;
+                    pack_idl_value(idl, &field.ty, field_value, buf)?;
+                },
+                Some(EnumFields::Tuple(types)) => for (index, ty) in types.iter().enumerate() {
+                    let field_value = fields_value.get(index).ok_or(anyhow!("Missing field {}.{}", name, index))?;
+                    pack_idl_value(idl, ty, field_value, buf)?;
+                },
+                None => {},
+            }
+        },
+    }
+    Ok(())
+}
+
+pub fn pack_idl_value(idl: &Idl, idl_type: &IdlType, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+    // Large integers can be given as strings, json numbers are limited to 64 bits
+    let int = |value: &Value| -> Result<i128> {
+        match value {
+            Value::Number(n) => n.as_i64().map(i128::from).or(n.as_u64().map(i128::from)).ok_or(anyhow!("Invalid integer: {}", n)),
+            Value::String(s) => Ok(s.parse::<i128>()?),
+            _ => Err(anyhow!("Invalid integer: {}", value)),
+        }
+    };
+    let float = |value: &Value| value.as_f64().ok_or(anyhow!("Invalid float: {}", value));
+
+    match idl_type {
+        IdlType::Bool => buf.push(value.as_bool().ok_or(anyhow!("Invalid bool: {}", value))? as u8),
+        IdlType::U8 => buf.extend_from_slice(&u8::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::I8 => buf.extend_from_slice(&i8::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::U16 => buf.extend_from_slice(&u16::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::I16 => buf.extend_from_slice(&i16::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::U32 => buf.extend_from_slice(&u32::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::I32 => buf.extend_from_slice(&i32::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::F32 => buf.extend_from_slice(&(float(value)? as f32).to_le_bytes()),
+        IdlType::U64 => buf.extend_from_slice(&u64::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::I64 => buf.extend_from_slice(&i64::try_from(int(value)?)?.to_le_bytes()),
+        IdlType::F64 => buf.extend_from_slice(&float(value)?.to_le_bytes()),
+        IdlType::U128 => buf.extend_from_slice(&match value {
+            Value::String(s) => s.parse::<u128>()?,
+            _ => u128::try_from(int(value)?)?,
+        }.to_le_bytes()),
+        IdlType::I128 => buf.extend_from_slice(&int(value)?.to_le_bytes()),
+        IdlType::String => String::serialize(&value.as_str().ok_or(anyhow!("Invalid string: {}", value))?.to_string(), buf)?,
+        IdlType::PublicKey => buf.extend_from_slice(&Pubkey::from_str(value.as_str().ok_or(anyhow!("Invalid pubkey: {}", value))?)?.to_bytes()),
+        IdlType::Bytes => Vec::<u8>::serialize(&serde_json::from_value::<Vec<u8>>(value.clone())?, buf)?,
+        IdlType::Option(inner) => match value {
+            Value::Null => buf.push(0),
+            _ => {
+                buf.push(1);
+                pack_idl_value(idl, inner, value, buf)?;
+            }
+        },
+        IdlType::Vec(inner) | IdlType::Array(inner, _) => {
+            let items = value.as_array().ok_or(anyhow!("Invalid array: {}", value))?;
+            match idl_type {
+                IdlType::Array(_, length) if items.len() != *length => return Err(anyhow!("Expected {} items, got {}", length, items.len())),
+                IdlType::Array(_, _) => {},
+                _ => buf.extend_from_slice(&u32::try_from(items.len())?.to_le_bytes()),
+            }
+            for item in items {
+                pack_idl_value(idl, inner, item, buf)?;
+            }
+        },
+        IdlType::Defined(name) => {
+            let type_def = find_defined_type(idl, name).ok_or(anyhow!("Type {} not found in IDL", name))?;
+            pack_idl_type_def(idl, type_def, value, buf)?;
+        },
+        _ => return Err(anyhow!("Unsupported IDL type: {:?}", idl_type)),
+    }
+    Ok(())
+}
+
+/// Decodes a borsh account to json values in the format of `pack_idl_account`, None for other serializations or unknown types
+pub fn decode_idl_account(idl: &Idl, raw_idl: &Value, strategy: DiscriminatorStrategy, data: &[u8]) -> Result<Option<(String, Value)>> {
+    Ok(decode_idl_account_len(idl, raw_idl, strategy, data)?.map(|(name, values, _)| (name, values)))
+}
+
+/// Like `decode_idl_account`, also returning the number of bytes decoded, including the discriminator
+pub fn decode_idl_account_len(idl: &Idl, raw_idl: &Value, strategy: DiscriminatorStrategy, data: &[u8]) -> Result<Option<(String, Value, usize)>> {
+    let (discriminator_len, idl_type_def) = match find_account_type(idl, strategy, data)? {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+    if type_serialization(raw_idl, &idl_type_def.name) != Serialization::Borsh {
+        return Ok(None)
+    }
+    let mut rest = &data[discriminator_len..];
+    let values = unpack_idl_type_def(idl, &idl_type_def, &mut rest)?;
+    Ok(Some((idl_type_def.name, values, data.len() - rest.len())))
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(anyhow!("Unexpected end of account data"))
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+fn unpack_idl_type_def(idl: &Idl, idl_type_def: &IdlTypeDefinition, data: &mut &[u8]) -> Result<Value> {
+    match &idl_type_def.ty {
+        Struct { fields } => {
+            let mut map = serde_json::Map::new();
+            for field in fields {
+                map.insert(field.name.clone(), unpack_idl_value(idl, &field.ty, data)?);
+            }
+            Ok(Value::Object(map))
+        },
+        Alias { value: alias } => unpack_idl_value(idl, alias, data),
+        Enum { variants } => {
+            let index = take(data, 1)?[0] as usize;
+            let variant = variants.get(index).ok_or(anyhow!("Invalid variant {} for enum {}", index, idl_type_def.name))?;
+            let fields_value = match &variant.fields {
+                Some(EnumFields::Named(fields)) => {
+                    let mut map = serde_json::Map::new();
+                    for field in fields {
+                        map.insert(field.name.clone(), unpack_idl_value(idl, &field.ty, data)?);
+                    }
+                    Value::Object(map)
+                },
+                Some(EnumFields::Tuple(types)) => Value::Array(types.iter().map(|ty| unpack_idl_value(idl, ty, data)).collect::<Result<Vec<Value>>>()?),
+                None => return Ok(Value::String(variant.name.clone())),
+            };
+            Ok(json!({ variant.name.clone(): fields_value }))
+        },
+    }
+}
+
+pub fn unpack_idl_value(idl: &Idl, idl_type: &IdlType, data: &mut &[u8]) -> Result<Value> {
+    macro_rules! le {
+        ($ty:ty) => { <$ty>::from_le_bytes(take(data, std::mem::size_of::<$ty>())?.try_into()?) };
+    }
+
+    Ok(match idl_type {
+        IdlType::Bool => Value::Bool(take(data, 1)?[0] != 0),
+        IdlType::U8 => json!(le!(u8)),
+        IdlType::I8 => json!(le!(i8)),
+        IdlType::U16 => json!(le!(u16)),
+        IdlType::I16 => json!(le!(i16)),
+        IdlType::U32 => json!(le!(u32)),
+        IdlType::I32 => json!(le!(i32)),
+        IdlType::F32 => json!(le!(f32)),
+        IdlType::U64 => json!(le!(u64)),
+        IdlType::I64 => json!(le!(i64)),
+        IdlType::F64 => json!(le!(f64)),
+        // Json numbers are limited to 64 bits
+        IdlType::U128 => Value::String(le!(u128).to_string()),
+        IdlType::I128 => Value::String(le!(i128).to_string()),
+        IdlType::String => {
+            let len = le!(u32) as usize;
+            Value::String(String::from_utf8(take(data, len)?.to_vec())?)
+        },
+        IdlType::PublicKey => Value::String(Pubkey::try_from(take(data, 32)?)?.to_string()),
+        IdlType::Bytes => {
+            let len = le!(u32) as usize;
+            json!(take(data, len)?)
+        },
+        IdlType::Option(inner) => match take(data, 1)?[0] {
+            0 => Value::Null,
+            _ => unpack_idl_value(idl, inner, data)?,
+        },
+        IdlType::Vec(inner) => {
+            let len = le!(u32) as usize;
+            Value::Array((0..len).map(|_| unpack_idl_value(idl, inner, data)).collect::<Result<Vec<Value>>>()?)
+        },
+        IdlType::Array(inner, len) => {
+            Value::Array((0..*len).map(|_| unpack_idl_value(idl, inner, data)).collect::<Result<Vec<Value>>>()?)
+        },
+        IdlType::Defined(name) => {
+            let type_def = find_defined_type(idl, name).ok_or(anyhow!("Type {} not found in IDL", name))?;
+            unpack_idl_type_def(idl, type_def, data)?
+        },
+        _ => return Err(anyhow!("Unsupported IDL type: {:?}", idl_type)),
+    })
+}
+
+pub fn find_account_type(idl: &Idl, strategy: DiscriminatorStrategy, data: &[u8]) -> Result<Option<(usize, IdlTypeDefinition)>> {
+    match strategy {
+        DiscriminatorStrategy::Anchor => {
+            let map = generate_discriminator_map(idl)?;
+            Ok(data.get(..8).and_then(|d| map.get(d)).map(|idl_type_def| (8, idl_type_def.clone())))
+        },
+        DiscriminatorStrategy::Key => {
+            let key = *data.first().ok_or(anyhow!("Account data is empty"))? as usize;
+
+            // Shank accounts start with a `key` field of a fieldless enum, the variant names the account type
+            let variant = idl.accounts.iter()
+                .find_map(|a| match &a.ty {
+                    Struct { fields } => fields.first().and_then(|f| match &f.ty {
+                        IdlType::Defined(name) if f.name == "key" => find_defined_type(idl, name),
+                        _ => None,
+                    }),
+                    _ => None,
+                })
+                .and_then(|key_type| match &key_type.ty {
+                    Enum { variants } => variants.get(key).map(|v| v.name.clone()),
+                    _ => None,
+                })
+                .ok_or(anyhow!("No key enum variant {} in IDL", key))?;
+
+            // Prefer an exact match, then the longest account name the variant starts with, eg. MetadataV1 -> Metadata
+            Ok(idl.accounts.iter()
+                .filter(|a| variant.starts_with(&a.name))
+                .max_by_key(|a| a.name.len())
+                .map(|idl_type_def| (0, idl_type_def.clone())))
+        },
+        DiscriminatorStrategy::None => Ok(None),
+    }
+}
+
+pub fn find_defined_type<'a>(idl: &'a Idl, name: &str) -> Option<&'a IdlTypeDefinition> {
+    idl.types.iter().chain(idl.accounts.iter()).find(|t| t.name == name)
+}
+
+pub fn unpack_idl_account(idl: &Idl, idl_type_def: &IdlTypeDefinition, data: &[u8]) -> Result<Vec<IdlAccountField>> {
+    let mut account_map: Vec<IdlAccountField> = vec!();
+    match &idl_type_def.ty {
+        Struct { fields } => {
+            let mut offset = 0usize;
+            unpack_idl_fields(idl, "", fields, data, &mut offset, &mut account_map)?;
             Ok(account_map)
         },
         _ => Err(anyhow!("Unsupported IDL type: {:?}", idl_type_def.ty)),
     }
 }
 
+fn unpack_idl_fields(idl: &Idl, prefix: &str, fields: &[IdlField], data: &[u8], offset: &mut usize, account_map: &mut Vec<IdlAccountField>) -> Result<()> {
+    for field in fields {
+        let name = format!("{}{}", prefix, field.name);
+
+        // Nested structs are flattened to dotted field names, so each of their fields stays editable
+        if let IdlType::Defined(defined) = &field.ty {
+            if let Some(IdlTypeDefinition { ty: Struct { fields }, .. }) = find_defined_type(idl, defined) {
+                unpack_idl_fields(idl, &format!("{}.", name), fields, data, offset, account_map)?;
+                continue;
+            }
+        }
+
+        // Accounts shorter than their type, eg. of an older program version, only have their first fields
+        let data_slice = match data.get(*offset..) {
+            Some(data_slice) if !data_slice.is_empty() => data_slice,
+            _ => break,
+        };
+        let mut idl_field = unpack_idl_field(IdlField { name, docs: None, ty: resolve_idl_type(idl, &field.ty) });
+        let mut rest = data_slice;
+        let value = unpack_idl_value(idl, &idl_field.orig_idl_field.ty, &mut rest).map_err(|e| anyhow!("{}: {}", idl_field.name, e))?;
+        idl_field.value = Some(FieldValue::from_json(idl, &idl_field.orig_idl_field.ty, &value)?);
+        idl_field.field_len = data_slice.len() - rest.len();
+        idl_field.offset = *offset;
+        *offset += idl_field.field_len;
+        account_map.push(idl_field);
+    }
+    Ok(())
+}
+
 pub fn unpack_idl_field(idl_field: IdlField) -> IdlAccountField {
     IdlAccountField {
         name: idl_field.name.clone(),
         value: None,
-        field_len: idl_type_len(&idl_field.ty),
+        field_len: 0,
+        offset: 0,
         orig_idl_field: idl_field
     }
 }
 
-pub fn idl_type_len(idl_type: &IdlType) -> usize {
-    match idl_type {
-        IdlType::Bool => mem::size_of::<u8>(),
-        IdlType::U8 => mem::size_of::<u8>(),
-        IdlType::I8 => mem::size_of::<i8>(),
-        IdlType::U16 => mem::size_of::<u16>(),
-        IdlType::I16 => mem::size_of::<i16>(),
-        IdlType::U32 => mem::size_of::<u32>(),
-        IdlType::I32 => mem::size_of::<i32>(),
-        IdlType::F32 => mem::size_of::<f32>(),
-        IdlType::U64 => mem::size_of::<u64>(),
-        IdlType::I64 => mem::size_of::<i64>(),
-        IdlType::F64 => unimplemented!(),
-        IdlType::U128 => mem::size_of::<u128>(),
-        IdlType::I128 => mem::size_of::<i128>(),
-        IdlType::U256 => unimplemented!(),
-        IdlType::I256 => unimplemented!(),
-        IdlType::Bytes => unimplemented!(),
-        IdlType::String => unimplemented!(),
-        IdlType::PublicKey => mem::size_of::<Pubkey>(),
-        IdlType::Defined(_defined) => unimplemented!(),
-        IdlType::Option(_) => unimplemented!(),
-        IdlType::Vec(idl_type) => {
-            idl_type_len(idl_type)
-        },
-        IdlType::Array(idl_type, length) => {
-            length*idl_type_len(idl_type)
-        },
-        IdlType::GenericLenArray(_, _) => unimplemented!(),
-        IdlType::Generic(_) => unimplemented!(),
-        IdlType::DefinedWithTypeArgs { name: _, args: _ } => unimplemented!(),
+pub fn resolve_idl_type(idl: &Idl, idl_type: &IdlType) -> IdlType {
+    // Fieldless enums are serialized as their u8 variant index, aliases as the aliased type
+    if let IdlType::Defined(name) = idl_type {
+        match find_defined_type(idl, name).map(|t| &t.ty) {
+            Some(Enum { variants }) if variants.iter().all(|v| v.fields.is_none()) => return IdlType::U8,
+            Some(Alias { value }) => return resolve_idl_type(idl, value),
+            _ => {}
+        }
+    }
+    idl_type.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_idl() -> Idl {
+        serde_json::from_value(json!({
+            "version": "0.1.0",
+            "name": "test",
+            "instructions": [],
+            "types": [
+                {
+                    "name": "Point",
+                    "type": { "kind": "struct", "fields": [
+                        { "name": "x", "type": "f64" },
+                        { "name": "y", "type": "f32" },
+                        { "name": "tags", "type": { "vec": "string" } }
+                    ]}
+                },
+                {
+                    "name": "Shape",
+                    "type": { "kind": "enum", "variants": [
+                        { "name": "Empty" },
+                        { "name": "Line", "fields": [{ "defined": "Point" }, { "defined": "Point" }] },
+                        { "name": "Circle", "fields": [{ "name": "center", "type": { "defined": "Point" } }, { "name": "radius", "type": "u64" }] }
+                    ]}
+                }
+            ]
+        })).unwrap()
+    }
+
+    fn round_trip(idl: &Idl, idl_type: &IdlType, value: Value) {
+        let mut buf = Vec::new();
+        pack_idl_value(idl, idl_type, &value, &mut buf).unwrap();
+        let mut data = buf.as_slice();
+        assert_eq!(unpack_idl_value(idl, idl_type, &mut data).unwrap(), value);
+        assert!(data.is_empty(), "{} bytes left for {:?}", data.len(), idl_type);
+    }
+
+    #[test]
+    fn round_trips_primitives() {
+        let idl = test_idl();
+        round_trip(&idl, &IdlType::Bool, json!(true));
+        round_trip(&idl, &IdlType::I8, json!(-8));
+        round_trip(&idl, &IdlType::U64, json!(u64::MAX));
+        round_trip(&idl, &IdlType::F32, json!(1.5));
+        round_trip(&idl, &IdlType::F64, json!(-0.1));
+        round_trip(&idl, &IdlType::U128, json!(u128::MAX.to_string()));
+        round_trip(&idl, &IdlType::I128, json!(i128::MIN.to_string()));
+        round_trip(&idl, &IdlType::String, json!("valid8"));
+        round_trip(&idl, &IdlType::PublicKey, json!(Pubkey::new_unique().to_string()));
+        round_trip(&idl, &IdlType::Bytes, json!([0, 1, 255]));
+    }
+
+    #[test]
+    fn round_trips_vecs_arrays_and_options() {
+        let idl = test_idl();
+        round_trip(&idl, &IdlType::Vec(Box::new(IdlType::String)), json!(["a", "", "bc"]));
+        round_trip(&idl, &IdlType::Array(Box::new(IdlType::U16), 3), json!([1, 2, 3]));
+        round_trip(&idl, &IdlType::Array(Box::new(IdlType::Option(Box::new(IdlType::U8))), 2), json!([null, 7]));
+        round_trip(&idl, &IdlType::Option(Box::new(IdlType::Vec(Box::new(IdlType::F64)))), json!([0.25, 1e10]));
+        round_trip(&idl, &IdlType::Option(Box::new(IdlType::String)), Value::Null);
+    }
+
+    #[test]
+    fn round_trips_nested_defined_types() {
+        let idl = test_idl();
+        let point = json!({ "x": 1.25, "y": -2.5, "tags": ["origin"] });
+        round_trip(&idl, &IdlType::Defined("Point".into()), point.clone());
+        round_trip(&idl, &IdlType::Defined("Shape".into()), json!("Empty"));
+        round_trip(&idl, &IdlType::Defined("Shape".into()), json!({ "Line": [point.clone(), { "x": 0.0, "y": 0.0, "tags": [] }] }));
+        round_trip(&idl, &IdlType::Vec(Box::new(IdlType::Defined("Shape".into()))), json!([{ "Circle": { "center": point, "radius": 3 } }, "Empty"]));
+    }
+
+    fn field(ty: IdlType) -> IdlAccountField {
+        IdlAccountField { name: "value".into(), value: None, field_len: 0, offset: 0, orig_idl_field: IdlField { name: "value".into(), docs: None, ty } }
+    }
+
+    #[test]
+    fn decodes_signed_and_128_bit_field_values() {
+        let idl = test_idl();
+        let cases = [
+            (IdlType::I8, (-1i8).to_le_bytes().to_vec(), "-1"),
+            (IdlType::I64, i64::MIN.to_le_bytes().to_vec(), "-9223372036854775808"),
+            (IdlType::U64, u64::MAX.to_le_bytes().to_vec(), "18446744073709551615"),
+            (IdlType::U128, u128::MAX.to_le_bytes().to_vec(), "340282366920938463463374607431768211455"),
+            (IdlType::I128, i128::MIN.to_le_bytes().to_vec(), "-170141183460469231731687303715884105728"),
+        ];
+        for (ty, data, shown) in cases {
+            let value = unpack_data_idl_type(&idl, &ty, &data).unwrap();
+            assert_eq!(value.to_string(), shown);
+
+            let mut decoded = field(ty.clone());
+            decoded.value = Some(value);
+            assert_eq!(decoded.to_bytes().unwrap(), data, "{:?}", ty);
+
+            let mut edited = field(ty.clone());
+            edited.edit(shown.to_string()).unwrap();
+            assert_eq!(edited.to_bytes().unwrap(), data, "{:?}", ty);
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_field_values() {
+        for (ty, value) in [(IdlType::U8, "256"), (IdlType::I8, "-129"), (IdlType::U64, "-1"), (IdlType::I64, "9223372036854775808"), (IdlType::Bool, "2")] {
+            let mut field = field(ty.clone());
+            assert!(field.edit(value.to_string()).and_then(|_| field.to_bytes()).is_err(), "{} was accepted for {:?}", value, ty);
+        }
+        let mut field = field(IdlType::I16);
+        field.value = Some(FieldValue::Signed(i128::from(i16::MAX) + 1));
+        assert!(field.to_bytes().is_err());
+    }
+
+    fn zero_copy_idl() -> Value {
+        let zero_copy = |name: &str, fields: Value, packed: bool| json!({
+            "name": name,
+            "type": { "kind": "struct", "fields": fields },
+            "serialization": if packed { "bytemuckunsafe" } else { "bytemuck" },
+            "repr": { "kind": "c", "packed": packed },
+        });
+        json!({
+            "version": "0.1.0",
+            "name": "zero_copy",
+            "instructions": [],
+            "accounts": [
+                zero_copy("Book", json!([
+                    { "name": "header", "type": "u8" },
+                    { "name": "inner", "type": { "defined": "Inner" } },
+                    { "name": "levels", "type": { "array": ["u32", 3] } },
+                    { "name": "items", "type": { "array": [{ "defined": "Inner" }, 2] } },
+                    { "name": "packed", "type": { "defined": "Packed" } },
+                    { "name": "tail", "type": "u8" },
+                ]), false),
+                zero_copy("Wide", json!([
+                    { "name": "flag", "type": "bool" },
+                    { "name": "big", "type": "u128" },
+                    { "name": "key", "type": "publicKey" },
+                    { "name": "after", "type": "u16" },
+                ]), false),
+                zero_copy("Unaligned", json!([
+                    { "name": "a", "type": "u8" },
+                    { "name": "b", "type": "i64" },
+                    { "name": "c", "type": "u16" },
+                ]), true),
+            ],
+            "types": [
+                zero_copy("Inner", json!([{ "name": "a", "type": "u8" }, { "name": "b", "type": "u64" }]), false),
+                zero_copy("Packed", json!([{ "name": "a", "type": "u8" }, { "name": "b", "type": "u64" }, { "name": "c", "type": "u16" }]), true),
+            ],
+        })
+    }
+
+    // Field name, offset and size
+    type FieldLayout = (&'static str, usize, usize);
+
+    #[test]
+    fn lays_out_zero_copy_fields_like_c() {
+        let raw_idl = zero_copy_idl();
+        let idl: Idl = serde_json::from_value(raw_idl.clone()).unwrap();
+        let cases: [(&str, usize, &[FieldLayout]); 3] = [
+            // u8 then u64 pads to 8, arrays are laid out item by item, packed structs are 1 byte aligned
+            ("Book", 88, &[
+                ("header", 0, 1), ("inner.a", 8, 1), ("inner.b", 16, 8), ("levels", 24, 12),
+                ("items[0].a", 40, 1), ("items[0].b", 48, 8), ("items[1].a", 56, 1), ("items[1].b", 64, 8),
+                ("packed.a", 72, 1), ("packed.b", 73, 8), ("packed.c", 81, 2), ("tail", 83, 1),
+            ]),
+            // u128 is 8 byte aligned on SBF and pubkeys are byte arrays
+            ("Wide", 64, &[("flag", 0, 1), ("big", 8, 16), ("key", 24, 32), ("after", 56, 2)]),
+            ("Unaligned", 11, &[("a", 0, 1), ("b", 1, 8), ("c", 9, 2)]),
+        ];
+
+        for (name, size, expected) in cases {
+            let idl_type_def = idl.accounts.iter().find(|account| account.name == name).unwrap();
+            let fields = match &idl_type_def.ty {
+                Struct { fields } => fields,
+                _ => unreachable!(),
+            };
+            assert_eq!(zero_copy_struct_layout(&idl, &raw_idl, fields, name == "Unaligned").unwrap().1, size, "size of {}", name);
+
+            let layout: Vec<(String, usize, usize)> = unpack_account(&idl, &raw_idl, idl_type_def, &vec![0; size]).unwrap()
+                .into_iter()
+                .map(|field| (field.name, field.offset, field.field_len))
+                .collect();
+            let expected: Vec<(String, usize, usize)> = expected.iter().map(|(field, offset, len)| (field.to_string(), *offset, *len)).collect();
+            assert_eq!(layout, expected, "layout of {}", name);
+        }
+    }
+
+    #[test]
+    fn packs_zero_copy_fields_at_their_offsets() {
+        let raw_idl = zero_copy_idl();
+        let idl: Idl = serde_json::from_value(raw_idl.clone()).unwrap();
+        let idl_type_def = idl.accounts.iter().find(|account| account.name == "Unaligned").unwrap();
+
+        let data = pack_idl_account(&idl, &raw_idl, idl_type_def, DiscriminatorStrategy::None, &json!({ "a": 1, "b": -2, "c": 3 })).unwrap();
+        assert_eq!(data, [[1].as_slice(), &(-2i64).to_le_bytes(), &3u16.to_le_bytes()].concat());
+    }
+
+    #[test]
+    fn rejects_wrong_array_length() {
+        let idl = test_idl();
+        let mut buf = Vec::new();
+        assert!(pack_idl_value(&idl, &IdlType::Array(Box::new(IdlType::U8), 2), &json!([1]), &mut buf).is_err());
     }
 }
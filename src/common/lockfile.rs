@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, fs, path::Path};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::serialization::b58;
+
+use super::{project_name::ProjectName, storage, AccountSchema};
+
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Pins the inputs of a project, so everyone installs the same fixtures and drift from live networks is noticed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    pub version: u32,
+    /// Remote compose sources by their id, eg. `git+https://host/repo.git#main`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub compose: BTreeMap<String, LockedSource>,
+    /// Cloned accounts and programs by pubkey, as they were fetched before any overrides
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, LockedAccount>,
+}
+
+/// The state of a cloned account when it was locked
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedAccount {
+    pub data_hash: String,
+    pub data_len: usize,
+    pub lamports: u64,
+    #[serde(with = "b58")]
+    pub owner: Pubkey,
+    pub slot: u64,
+}
+
+impl LockedAccount {
+    pub fn new(account: &AccountSchema, slot: u64) -> Self {
+        Self {
+            data_hash: storage::data_hash(&account.data),
+            data_len: account.data.len(),
+            lamports: account.lamports,
+            owner: account.owner,
+            slot,
+        }
+    }
+
+    /// Describes how `other` differs from this state, the slot is expected to change
+    pub fn changes(&self, other: &LockedAccount) -> Vec<String> {
+        let mut changes = vec![];
+        if self.owner != other.owner {
+            changes.push(format!("owner {} -> {}", self.owner, other.owner));
+        }
+        if self.lamports != other.lamports {
+            changes.push(format!("lamports {} -> {}", self.lamports, other.lamports));
+        }
+        if self.data_len != other.data_len {
+            changes.push(format!("data length {} -> {}", self.data_len, other.data_len));
+        }
+        if self.data_hash != other.data_hash {
+            changes.push(format!("data {} -> {}", &self.data_hash[..8], &other.data_hash[..8]));
+        }
+        changes
+    }
+}
+
+/// What install does when a cloned account differs from the lockfile
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftPolicy {
+    /// Fail without saving the fetched accounts
+    #[default]
+    Error,
+    /// Print the changes and use the fetched accounts
+    Warn,
+}
+
+/// The resolved version of a remote compose source
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedSource {
+    /// Commit of a git revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// sha256 of a bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self { version: LOCKFILE_VERSION, compose: BTreeMap::new(), accounts: BTreeMap::new() }
+    }
+}
+
+impl Lockfile {
+    /// Reads the lockfile of a project, an empty one if it doesn't exist yet
+    pub fn read(project_name: &ProjectName) -> Result<Self> {
+        let path = project_name.to_lockfile();
+        if !Path::new(&path).exists() {
+            return Ok(Self::default())
+        }
+        let lockfile: Self = serde_json::from_slice(&fs::read(&path)?).map_err(|e| anyhow!("Invalid {}: {}", path, e))?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(anyhow!("{} version {} is newer than supported version {}, please update valid8", path, lockfile.version, LOCKFILE_VERSION))
+        }
+        Ok(lockfile)
+    }
+
+    /// Compares fetched accounts and their slots with the locked state, returns a description of each changed account
+    pub fn drift(&self, fetched: &[(&AccountSchema, u64)]) -> Vec<String> {
+        fetched
+            .iter()
+            .filter_map(|(account, slot)| {
+                let locked = self.accounts.get(&account.pubkey.to_string())?;
+                let changes = locked.changes(&LockedAccount::new(account, *slot));
+                match changes.is_empty() {
+                    true => None,
+                    false => Some(format!("{} (locked at slot {}, fetched at {}): {}", account.pubkey, locked.slot, slot, changes.join(", "))),
+                }
+            })
+            .collect()
+    }
+
+    /// Locks the fetched state of an account, replacing its locked state
+    pub fn lock(&mut self, account: &AccountSchema, slot: u64) {
+        self.accounts.insert(account.pubkey.to_string(), LockedAccount::new(account, slot));
+    }
+
+    /// Locks fetched accounts that aren't locked yet, returns whether any were added
+    pub fn lock_new(&mut self, fetched: &[(&AccountSchema, u64)]) -> bool {
+        let mut added = false;
+        for (account, slot) in fetched {
+            if !self.accounts.contains_key(&account.pubkey.to_string()) {
+                self.lock(account, *slot);
+                added = true;
+            }
+        }
+        added
+    }
+
+    pub fn save(&self, project_name: &ProjectName) -> Result<()> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        fs::write(project_name.to_lockfile(), json)?;
+        Ok(())
+    }
+}
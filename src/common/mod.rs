@@ -11,4 +11,20 @@ pub mod project_name;
 // pub use project_name::*;
 
 pub mod idl;
-// pub use idl::*;
\ No newline at end of file
+// pub use idl::*;
+
+pub mod pda;
+
+pub mod anchor_toml;
+
+pub mod bundle;
+
+pub mod storage;
+
+pub mod compose;
+
+pub mod lockfile;
+
+pub mod remote;
+
+pub mod diff;
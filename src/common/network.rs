@@ -1,29 +1,33 @@
 use std::{fmt::Display, str::FromStr};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use anyhow::{Result, Error};
-use dialoguer::{Input, Select};
+use anyhow::{anyhow, Result};
 use solana_client::rpc_client::RpcClient;
 
-use crate::context::Valid8Context;
-
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Network {
     Mainnet,
     Devnet,
     #[default]
     Local,
-    Custom(String)
+    Custom(String),
+    /// Not cloned from any network: imported from account json files, added from .so files or created from IDL types
+    Imported,
 }
 
 impl Network {
-    pub fn client(&self) -> RpcClient {
+    /// Creates an RPC client, offline contexts fail instead of connecting
+    pub fn client(&self, offline: bool) -> Result<RpcClient> {
+        if offline {
+            return Err(anyhow!("Offline mode, not connecting to {}", self))
+        }
         let url = match self {
+            Network::Imported => return Err(anyhow!("Imported accounts aren't on a network, import them again to update them")),
             Network::Mainnet => "https://api.mainnet-beta.solana.com",
             Network::Devnet => "https://api.devnet.solana.com",
             Network::Local => "http://localhost:8899",
             Network::Custom(u) => u,
         };
-        RpcClient::new(url.to_string()) 
+        Ok(RpcClient::new(url.to_string()))
     }
 }
 
@@ -33,6 +37,7 @@ impl Display for Network {
             Network::Mainnet => "mainnet",
             Network::Devnet => "devnet",
             Network::Local => "local",
+            Network::Imported => "imported",
             Network::Custom(n) => n
         })?;
         Ok(())
@@ -47,6 +52,7 @@ impl FromStr for Network {
             "mainnet" => Network::Mainnet,
             "devnet" => Network::Devnet,
             "local" => Network::Local,
+            "imported" => Network::Imported,
             s => Network::Custom(s.to_string())
         })
     }
@@ -65,36 +71,3 @@ impl<'de> Deserialize<'de> for Network {
             .map_err(serde::de::Error::custom))
     }
 }
-
-pub fn get(ctx: &Valid8Context) -> Result<Network> {
-    let mut items: Vec<String> = vec!["mainnet".into(), "devnet".into(), "custom".into(), "exit".into()];
-    for network in ctx.networks.iter() {
-        if !items.contains(&network.to_string()) {
-            items.push(network.to_string())
-        }
-    }
-    let selection = Select::new()
-        .with_prompt("Select a network")
-        .items(&items)
-        .interact()?;
-    
-    match selection {
-        0 => Ok(Network::Mainnet),
-        1 => Ok(Network::Devnet),
-        2 => custom_network(),
-        3 => Err(Error::msg("Exit")),
-        _ => if items.len() > selection {
-            Ok(Network::Custom(items[selection].clone()))
-        } else {
-            Err(Error::msg("Invalid network selection"))
-        }
-    }   
-}
-
-pub fn custom_network() -> Result<Network> {
-    let address = Input::new()
-        .with_prompt("Network address")
-        .with_initial_text("https://")
-        .interact()?;
-    Ok(Network::Custom(address))
-}
\ No newline at end of file
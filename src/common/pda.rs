@@ -0,0 +1,236 @@
+use std::{collections::HashMap, str::FromStr};
+use anyhow::{anyhow, Result};
+use anchor_lang::anchor_syn::idl::types::{Idl, IdlAccountItem, IdlPda, IdlSeed, IdlType};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::context::Valid8Context;
+
+use super::{idl::{self, FieldValue}, AccountSchema};
+
+// Seed values of a PDA definition tried when looking for the seeds of an account, beyond that it's skipped
+const MAX_SEED_COMBINATIONS: usize = 4096;
+
+pub fn find_idl_pda(idl: &Idl, account_name: &str) -> Result<IdlPda> {
+    fn find_in_items(items: &[IdlAccountItem], account_name: &str) -> Option<IdlPda> {
+        items.iter().find_map(|item| match item {
+            IdlAccountItem::IdlAccount(account) if account.name == account_name => account.pda.clone(),
+            IdlAccountItem::IdlAccount(_) => None,
+            IdlAccountItem::IdlAccounts(accounts) => find_in_items(&accounts.accounts, account_name),
+        })
+    }
+
+    // The same account usually shows up in several instructions, they all share the seed definition
+    idl.instructions
+        .iter()
+        .find_map(|ix| find_in_items(&ix.accounts, account_name))
+        .ok_or(anyhow!("No PDA seeds for account {} in IDL {}", account_name, idl.name))
+}
+
+fn collect_pdas(items: &[IdlAccountItem], pdas: &mut Vec<IdlPda>) {
+    for item in items {
+        match item {
+            IdlAccountItem::IdlAccount(account) => pdas.extend(account.pda.clone()),
+            IdlAccountItem::IdlAccounts(accounts) => collect_pdas(&accounts.accounts, pdas),
+        }
+    }
+}
+
+/// Finds the seeds an account address was derived from, trying all PDA definitions of the IDL with values from the
+/// fields of the account and the pubkeys of the context. Returns the seeds without the bump, and the bump
+pub fn find_pda_seeds(ctx: &Valid8Context, idl: &Idl, raw_idl: &Value, program_id: &Pubkey, account: &AccountSchema) -> Option<(Vec<Vec<u8>>, u8)> {
+    let strategy = ctx.discriminator_strategy(program_id, idl);
+    let fields = match idl::decode_idl_account(idl, raw_idl, strategy, &account.data).ok().flatten() {
+        Some((_, Value::Object(fields))) => fields,
+        _ => serde_json::Map::new(),
+    };
+
+    let mut pdas = vec![];
+    for ix in idl.instructions.iter() {
+        collect_pdas(&ix.accounts, &mut pdas);
+    }
+    let mut tried = vec![];
+    for idl_pda in pdas.into_iter().filter(|idl_pda| idl_pda.program_id.is_none()) {
+        // The same account usually shows up in several instructions
+        let definition = format!("{:?}", idl_pda.seeds);
+        if tried.contains(&definition) {
+            continue
+        }
+        tried.push(definition);
+
+        let candidates: Vec<Vec<Vec<u8>>> = idl_pda.seeds.iter().map(|seed| seed_candidates(ctx, (idl, raw_idl, strategy), program_id, seed, &fields)).collect();
+        let combinations: usize = candidates.iter().map(|values| values.len()).product();
+        if combinations == 0 || combinations > MAX_SEED_COMBINATIONS {
+            continue
+        }
+        for combination in 0..combinations {
+            let mut rest = combination;
+            let seeds: Vec<Vec<u8>> = candidates
+                .iter()
+                .map(|values| {
+                    let value = values[rest % values.len()].clone();
+                    rest /= values.len();
+                    value
+                })
+                .collect();
+            let seed_slices = seeds.iter().map(|s| s.as_slice()).collect::<Vec<&[u8]>>();
+            if let Some((address, bump)) = Pubkey::try_find_program_address(&seed_slices, program_id) {
+                if address == account.pubkey {
+                    return Some((seeds, bump))
+                }
+            }
+        }
+    }
+    None
+}
+
+fn field_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+// Possible values of a seed, from the fields of the account with the name of the seed, or any pubkey known for account seeds
+fn seed_candidates(ctx: &Valid8Context, (idl, raw_idl, strategy): (&Idl, &Value, idl::DiscriminatorStrategy), program_id: &Pubkey, seed: &IdlSeed, fields: &serde_json::Map<String, Value>) -> Vec<Vec<u8>> {
+    let field_bytes = |idl_type: &IdlType, name: &str| fields.get(name).and_then(|value| seed_bytes(idl_type, &field_string(value)).ok());
+    let mut candidates: Vec<Vec<u8>> = match seed {
+        IdlSeed::Const(seed_const) => const_seed_bytes(&seed_const.ty, &seed_const.value).into_iter().collect(),
+        IdlSeed::Arg(seed_arg) => {
+            let name = seed_arg.path.rsplit('.').next().unwrap_or(&seed_arg.path);
+            field_bytes(&seed_arg.ty, name).into_iter().collect()
+        },
+        IdlSeed::Account(seed_account) => match seed_account.path.split_once('.') {
+            // `pool.mint` is read from the account in the `pool` field
+            Some((account_name, field_path)) => fields
+                .get(account_name)
+                .and_then(|pubkey| Pubkey::from_str(&field_string(pubkey)).ok())
+                .and_then(|pubkey| ctx.accounts.iter().find(|acc| acc.pubkey == pubkey))
+                .and_then(|other| idl::decode_idl_account(idl, raw_idl, strategy, &other.data).ok().flatten())
+                .and_then(|(_, values)| values.get(field_path).and_then(|value| seed_bytes(&seed_account.ty, &field_string(value)).ok()))
+                .into_iter()
+                .collect(),
+            None if seed_account.ty != IdlType::PublicKey => field_bytes(&seed_account.ty, &seed_account.path).into_iter().collect(),
+            None => {
+                let mut pubkeys: Vec<Vec<u8>> = field_bytes(&seed_account.ty, &seed_account.path).into_iter().collect();
+                pubkeys.extend(fields.values().filter_map(|value| Pubkey::from_str(&field_string(value)).ok()).map(|pubkey| pubkey.to_bytes().to_vec()));
+                pubkeys.push(program_id.to_bytes().to_vec());
+                pubkeys.extend(ctx.programs.iter().chain(ctx.accounts.iter()).map(|acc| acc.pubkey.to_bytes().to_vec()));
+                pubkeys
+            },
+        },
+    };
+    let mut seen = vec![];
+    candidates.retain(|candidate| match seen.contains(candidate) {
+        true => false,
+        false => { seen.push(candidate.clone()); true },
+    });
+    candidates
+}
+
+pub fn derive_pda(ctx: &Valid8Context, idl: &Idl, program_id: &Pubkey, account_name: &str, seeds: &HashMap<String, String>) -> Result<(Pubkey, u8)> {
+    let idl_pda = find_idl_pda(idl, account_name)?;
+
+    let mut missing = vec![];
+    let seed_bytes = idl_pda.seeds
+        .iter()
+        .filter_map(|seed| match resolve_seed(ctx, idl, seed, seeds) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => { missing.push(e.to_string()); None },
+        })
+        .collect::<Vec<Vec<u8>>>();
+    if !missing.is_empty() {
+        return Err(anyhow!("Could not resolve seeds for {}:\n{}", account_name, missing.join("\n")));
+    }
+
+    let pda_program_id = match &idl_pda.program_id {
+        Some(seed) => Pubkey::try_from(resolve_seed(ctx, idl, seed, seeds)?.as_slice())
+            .map_err(|_| anyhow!("Invalid program id seed for {}", account_name))?,
+        None => *program_id,
+    };
+
+    let seed_slices = seed_bytes.iter().map(|s| s.as_slice()).collect::<Vec<&[u8]>>();
+    Ok(Pubkey::find_program_address(&seed_slices, &pda_program_id))
+}
+
+fn resolve_seed(ctx: &Valid8Context, idl: &Idl, seed: &IdlSeed, seeds: &HashMap<String, String>) -> Result<Vec<u8>> {
+    match seed {
+        IdlSeed::Const(seed_const) => const_seed_bytes(&seed_const.ty, &seed_const.value),
+        IdlSeed::Arg(seed_arg) => {
+            let value = seeds.get(&seed_arg.path).ok_or(anyhow!("  missing arg seed: --seed {}=<{:?}>", seed_arg.path, seed_arg.ty))?;
+            seed_value_bytes(ctx, &seed_arg.ty, value)
+        },
+        IdlSeed::Account(seed_account) => {
+            if let Some(value) = seeds.get(&seed_account.path) {
+                return seed_value_bytes(ctx, &seed_account.ty, value)
+            }
+
+            // Paths like `pool.mint` read the field from the account passed for `pool`, decoded with the IDL
+            let missing = || anyhow!("  missing account seed: --seed {}=<{:?}>", seed_account.path, seed_account.ty);
+            let (account_name, field_path) = seed_account.path.split_once('.').ok_or_else(missing)?;
+            let pubkey = ctx.resolve(seeds.get(account_name).ok_or_else(missing)?)?;
+            let account_type = seed_account.account.as_ref().ok_or_else(missing)?;
+
+            let account = ctx.accounts
+                .iter()
+                .find(|acc| acc.pubkey == pubkey)
+                .ok_or(anyhow!("  account {} for seed {} not found in context", pubkey, seed_account.path))?;
+            let idl_type_def = idl.accounts
+                .iter()
+                .find(|a| a.name == *account_type)
+                .ok_or(anyhow!("  account type {} not found in IDL", account_type))?;
+            // The discriminator length depends on the strategy of the owning program, it's only skipped if it matches
+            let strategy = ctx.discriminator_strategy(&account.owner, idl);
+            let discriminator_len = idl::find_account_type(idl, strategy, &account.data)?
+                .filter(|(_, found)| found.name == *account_type)
+                .map_or(0, |(discriminator_len, _)| discriminator_len);
+            let field = idl::unpack_idl_account(idl, idl_type_def, &account.data[discriminator_len..])?
+                .into_iter()
+                .find(|field| field.name == field_path)
+                .ok_or(anyhow!("  field {} not found in {}", field_path, account_type))?;
+
+            match field.value {
+                Some(FieldValue::String(s)) => Ok(s.into_bytes()),
+                _ => field.to_bytes(),
+            }
+        },
+    }
+}
+
+// Pubkey seeds can also be given as the label of an account of the config
+fn seed_value_bytes(ctx: &Valid8Context, idl_type: &IdlType, value: &str) -> Result<Vec<u8>> {
+    match idl_type {
+        IdlType::PublicKey => Ok(ctx.resolve(value)?.to_bytes().to_vec()),
+        idl_type => seed_bytes(idl_type, value),
+    }
+}
+
+pub fn seed_bytes(idl_type: &IdlType, value: &str) -> Result<Vec<u8>> {
+    Ok(match idl_type {
+        IdlType::String => value.as_bytes().to_vec(),
+        IdlType::PublicKey => Pubkey::from_str(value)?.to_bytes().to_vec(),
+        IdlType::Bool => vec![value.parse::<bool>()? as u8],
+        IdlType::U8 => value.parse::<u8>()?.to_le_bytes().to_vec(),
+        IdlType::I8 => value.parse::<i8>()?.to_le_bytes().to_vec(),
+        IdlType::U16 => value.parse::<u16>()?.to_le_bytes().to_vec(),
+        IdlType::I16 => value.parse::<i16>()?.to_le_bytes().to_vec(),
+        IdlType::U32 => value.parse::<u32>()?.to_le_bytes().to_vec(),
+        IdlType::I32 => value.parse::<i32>()?.to_le_bytes().to_vec(),
+        IdlType::U64 => value.parse::<u64>()?.to_le_bytes().to_vec(),
+        IdlType::I64 => value.parse::<i64>()?.to_le_bytes().to_vec(),
+        IdlType::U128 => value.parse::<u128>()?.to_le_bytes().to_vec(),
+        IdlType::I128 => value.parse::<i128>()?.to_le_bytes().to_vec(),
+        // Byte seeds are given as a json array, eg. [1,2,3]
+        IdlType::Bytes | IdlType::Vec(_) | IdlType::Array(_, _) => serde_json::from_str::<Vec<u8>>(value)?,
+        _ => return Err(anyhow!("Unsupported seed type: {:?}", idl_type)),
+    })
+}
+
+fn const_seed_bytes(idl_type: &IdlType, value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(s) => seed_bytes(idl_type, s),
+        Value::Number(n) => seed_bytes(idl_type, &n.to_string()),
+        Value::Array(_) => Ok(serde_json::from_value::<Vec<u8>>(value.clone())?),
+        _ => Err(anyhow!("Unsupported const seed value: {}", value)),
+    }
+}
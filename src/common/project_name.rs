@@ -1,11 +1,28 @@
 use std::{fmt::Display, str::FromStr};
+use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
+/// Name of a project, its config, lockfile and resources directory are named after it in the current directory
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "RawProjectName")]
 pub struct ProjectName {
     name: String
 }
 
+// Project names are read from configs and bundles, they're validated like names given on the command line
+#[derive(Deserialize)]
+struct RawProjectName {
+    name: String
+}
+
+impl TryFrom<RawProjectName> for ProjectName {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawProjectName) -> Result<Self, Self::Error> {
+        Self::from_str(&raw.name)
+    }
+}
+
 impl ProjectName {
     pub const DEFAULT: &'static str = "valid8";
 
@@ -15,6 +32,9 @@ impl ProjectName {
     pub fn to_resources(&self) -> String {
         format!(".{}/", self.name)
     }
+    pub fn to_lockfile(&self) -> String {
+        format!("{}.lock", self.name)
+    }
     // pub fn to_ledger_path(&self) -> String {
     //     format!("{}{}_ledger", self.to_resources(), self.name)
     // }
@@ -31,9 +51,13 @@ impl FromStr for ProjectName {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The name is used in paths, it can't point to another directory
+        if s.is_empty() || s.starts_with('.') || s.contains(['/', '\\']) || s.contains("..") {
+            return Err(anyhow!("Invalid project name {:?}, it can't be empty, start with a dot or contain a path", s))
+        }
         Ok(Self{ name: s.to_string() })
     }
-    
+
 }
 
 impl Display for ProjectName {
@@ -0,0 +1,133 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+use anyhow::{anyhow, Result};
+
+use super::{bundle::{self, BundleManifest}, storage};
+
+const MANIFEST_NAME: &str = "bundle-manifest.json";
+
+fn compose_cache() -> Result<PathBuf> {
+    Ok(storage::cache_dir()?.join("compose"))
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git, is it installed? {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn has_commit(repo: &str, commit: &str) -> bool {
+    git(&["--git-dir", repo, "cat-file", "-e", &format!("{}^{{commit}}", commit)]).is_ok()
+}
+
+/// Checks out a git revision into the cache, returning the checkout and its commit.
+/// A locked commit is used instead of resolving the revision again, and isn't fetched if it's already cached.
+pub fn git_checkout(url: &str, rev: &str, locked_commit: Option<&str>, offline: bool) -> Result<(PathBuf, String)> {
+    if rev.starts_with('-') {
+        return Err(anyhow!("Invalid revision {}", rev))
+    }
+    let cache = compose_cache()?.join("git");
+    let url_hash = storage::data_hash(url.as_bytes());
+    if let Some(commit) = locked_commit {
+        let checkout = cache.join(format!("{}-{}", &url_hash[..16], commit));
+        if checkout.exists() {
+            return Ok((checkout, commit.to_string()))
+        }
+    }
+    // Local repositories don't need network access
+    if offline && !url.starts_with("file://") && !Path::new(url).exists() {
+        return Err(anyhow!("Offline mode, {}#{} is not in the cache", url, rev))
+    }
+
+    // One mirror per url, shared by all revisions
+    let mirror = cache.join(&url_hash[..16]);
+    let repo = mirror.to_str().ok_or(anyhow!("Invalid cache path {}", mirror.display()))?;
+    if mirror.exists() {
+        if locked_commit.is_none_or(|commit| !has_commit(repo, commit)) {
+            git(&["--git-dir", repo, "fetch", "--quiet", "--prune", "origin"])?;
+        }
+    } else {
+        fs::create_dir_all(&cache)?;
+        // Urls come from included configs, `--` keeps them from being read as options
+        git(&["clone", "--quiet", "--mirror", "--", url, repo])?;
+    }
+    let commit = match locked_commit {
+        Some(commit) if has_commit(repo, commit) => commit.to_string(),
+        Some(commit) => return Err(anyhow!("Locked commit {} not found in {}", commit, url)),
+        None => git(&["--git-dir", repo, "rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+            .map_err(|_| anyhow!("Revision {} not found in {}", rev, url))?,
+    };
+
+    let checkout = cache.join(format!("{}-{}", &url_hash[..16], commit));
+    if !checkout.exists() {
+        // Checkouts are extracted next to their final path and renamed, so they're never partial
+        let archive = Command::new("git").args(["--git-dir", repo, "archive", "--format=tar", &commit]).output()?;
+        if !archive.status.success() {
+            return Err(anyhow!("git archive {} failed: {}", commit, String::from_utf8_lossy(&archive.stderr).trim()))
+        }
+        let tmp = tempfile::tempdir_in(&cache)?;
+        tar::Archive::new(archive.stdout.as_slice()).unpack(tmp.path())?;
+        fs::rename(tmp.keep(), &checkout)?;
+    }
+    Ok((checkout, commit))
+}
+
+fn read_location(location: &str, dir: &Path, offline: bool) -> Result<Vec<u8>> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        if offline {
+            return Err(anyhow!("Offline mode, not downloading {}", location))
+        }
+        let response = reqwest::blocking::get(location)?.error_for_status()?;
+        return Ok(response.bytes()?.to_vec())
+    }
+    let path = match location.strip_prefix("file://") {
+        Some(path) => PathBuf::from(path),
+        None => dir.join(location),
+    };
+    fs::read(&path).map_err(|e| anyhow!("Can't read bundle {}: {}", path.display(), e))
+}
+
+/// Fetches and unpacks a bundle into the cache, returning the path of its config and the bundle hash.
+/// Bundles are paths relative to `dir`, `file://` or http(s) urls, a locked bundle must have the locked hash.
+pub fn bundle_checkout(location: &str, dir: &Path, locked_hash: Option<&str>, offline: bool) -> Result<(PathBuf, String)> {
+    let cache = compose_cache()?.join("bundles");
+    // Local bundles are read again so changes are noticed, downloads are skipped while the locked bundle is cached
+    let downloaded = location.starts_with("http://") || location.starts_with("https://");
+    if let Some(hash) = locked_hash.filter(|_| downloaded) {
+        let checkout = cache.join(hash);
+        if checkout.exists() {
+            let manifest: BundleManifest = serde_json::from_slice(&fs::read(checkout.join(MANIFEST_NAME))?)?;
+            return Ok((checkout.join(manifest.project_name.to_config()), hash.to_string()))
+        }
+    }
+
+    let data = read_location(location, dir, offline)?;
+    let hash = storage::data_hash(&data);
+    if let Some(locked_hash) = locked_hash.filter(|locked_hash| *locked_hash != hash) {
+        return Err(anyhow!("Bundle {} changed, locked hash {} but found {}, run `valid8 compose --update` to use the new bundle", location, locked_hash, hash))
+    }
+
+    let checkout = cache.join(&hash);
+    if !checkout.exists() {
+        let bundle_path = cache.join(format!("{}.v8", hash));
+        storage::write_atomic(&bundle_path, &data)?;
+        let (manifest, files) = bundle::read_bundle(&bundle_path)?;
+        let tmp = tempfile::tempdir_in(&cache)?;
+        for (file, data) in files.iter() {
+            let path = tmp.path().join(file);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, data)?;
+        }
+        fs::write(tmp.path().join(MANIFEST_NAME), serde_json::to_vec_pretty(&manifest)?)?;
+        fs::rename(tmp.keep(), &checkout)?;
+        fs::remove_file(bundle_path)?;
+    }
+    let manifest: BundleManifest = serde_json::from_slice(&fs::read(checkout.join(MANIFEST_NAME))?)?;
+    Ok((checkout.join(manifest.project_name.to_config()), hash))
+}
@@ -0,0 +1,351 @@
+use std::{collections::BTreeSet, env, fs, io::Write, path::{Path, PathBuf}, str::FromStr};
+use anchor_lang::anchor_syn::idl::types::Idl;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use solana_sdk::{hash::hash, pubkey::Pubkey};
+
+use crate::serialization::b58;
+
+use super::{idl::{self, DiscriminatorStrategies, DiscriminatorStrategy}, project_name::ProjectName, AccountSchema, Network};
+
+const CACHE_POINTER_EXTENSION: &str = "cache.json";
+const BINCODE_EXTENSION: &str = "bin";
+const JSON_EXTENSION: &str = "account.json";
+const JSON_BYTES_PER_LINE: usize = 32;
+const PROJECTS_NAME: &str = "projects.json";
+const PRISTINE_DIR: &str = "pristine";
+
+/// How account data is stored in the resources directory of a project
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    /// One bincode file per account
+    #[default]
+    Bincode,
+    /// A pointer file per account, with the data in the shared content addressed cache
+    Cache,
+    /// One readable json file per account, with hex data and decoded IDL values
+    Json,
+}
+
+/// Account metadata pointing to its data in the shared cache
+#[derive(Serialize, Deserialize, Debug)]
+struct CachePointer {
+    #[serde(with = "b58")]
+    pubkey: Pubkey,
+    network: Network,
+    lamports: u64,
+    #[serde(with = "b58")]
+    owner: Pubkey,
+    executable: bool,
+    rent_epoch: u64,
+    data_len: usize,
+    #[serde(deserialize_with = "deserialize_data_hash")]
+    data_hash: String,
+}
+
+// Pointers are read from disc, the hash is part of the blob path so it must be a plain hex sha256
+fn deserialize_data_hash<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    let data_hash = String::deserialize(d)?;
+    match data_hash.len() == 64 && data_hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        true => Ok(data_hash),
+        false => Err(serde::de::Error::custom(format!("invalid data hash {:?}, expected 64 lowercase hex characters", data_hash))),
+    }
+}
+
+/// Readable account file, the data is hex in fixed width lines so diffs point at the changed bytes
+#[derive(Serialize, Deserialize, Debug)]
+struct AccountFile {
+    #[serde(with = "b58")]
+    pubkey: Pubkey,
+    network: Network,
+    lamports: u64,
+    #[serde(with = "b58")]
+    owner: Pubkey,
+    executable: bool,
+    rent_epoch: u64,
+    /// Informational only, the data is always read from `data`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decoded: Option<DecodedAccount>,
+    data_len: usize,
+    data: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DecodedAccount {
+    account_type: String,
+    values: Value,
+}
+
+/// The shared cache directory, `VALID8_CACHE_DIR` or `~/.cache/valid8`
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = env::var("VALID8_CACHE_DIR") {
+        return Ok(PathBuf::from(dir))
+    }
+    let cache_home = env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map_err(|_| anyhow!("No cache directory found, set VALID8_CACHE_DIR"))?;
+    Ok(cache_home.join("valid8"))
+}
+
+/// Hex sha256 hash, used to address cached data
+pub fn data_hash(data: &[u8]) -> String {
+    hash(data).to_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn blob_path(cache: &Path, data_hash: &str) -> PathBuf {
+    cache.join("blobs").join(&data_hash[..2]).join(data_hash)
+}
+
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    // Other processes may read or write the same file, so it's renamed in place when complete
+    let dir = path.parent().ok_or(anyhow!("Invalid path {}", path.display()))?;
+    fs::create_dir_all(dir)?;
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(data)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+fn write_blob(cache: &Path, data: &[u8]) -> Result<String> {
+    let data_hash = data_hash(data);
+    let path = blob_path(cache, &data_hash);
+    if !path.exists() {
+        write_atomic(&path, data)?;
+    }
+    Ok(data_hash)
+}
+
+fn read_blob(cache: &Path, data_hash: &str) -> Result<Vec<u8>> {
+    let path = blob_path(cache, data_hash);
+    let data = fs::read(&path).map_err(|e| anyhow!("Cache blob {} missing: {}", path.display(), e))?;
+    if self::data_hash(&data) != data_hash {
+        return Err(anyhow!("Cache blob {} is corrupted, its hash doesn't match", path.display()))
+    }
+    Ok(data)
+}
+
+fn to_hex_lines(data: &[u8]) -> Vec<String> {
+    data.chunks(JSON_BYTES_PER_LINE)
+        .map(|line| line.iter().map(|b| format!("{:02x}", b)).collect())
+        .collect()
+}
+
+fn from_hex_lines(lines: &[String]) -> Result<Vec<u8>> {
+    let hex: String = lines.concat();
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(anyhow!("Account data must be an even number of hex digits"))
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("Invalid hex in account data: {}", &hex[i..i + 2])))
+        .collect()
+}
+
+fn decode_account(project_name: &ProjectName, discriminators: Option<&DiscriminatorStrategies>, account: &AccountSchema) -> Option<DecodedAccount> {
+    // Decoding is best effort, accounts without a borsh IDL type are kept as hex only
+    let raw_idl: Value = serde_json::from_slice(&fs::read(format!("{}{}.idl.json", project_name.to_resources(), account.owner)).ok()?).ok()?;
+    let idl: Idl = serde_json::from_value(raw_idl.clone()).ok()?;
+    let strategy = DiscriminatorStrategy::configured(discriminators, &account.owner, &idl);
+    idl::decode_idl_account(&idl, &raw_idl, strategy, &account.data)
+        .ok()
+        .flatten()
+        .map(|(account_type, values)| DecodedAccount { account_type, values })
+}
+
+fn account_path(project_name: &ProjectName, pubkey: &Pubkey, extension: &str) -> PathBuf {
+    Path::new(&project_name.to_resources()).join(format!("{}.{}", pubkey, extension))
+}
+
+fn read_projects(cache: &Path) -> Result<BTreeSet<PathBuf>> {
+    match fs::read(cache.join(PROJECTS_NAME)) {
+        Ok(projects) => Ok(serde_json::from_slice(&projects)?),
+        Err(_) => Ok(BTreeSet::new()),
+    }
+}
+
+fn register_project(cache: &Path, project_name: &ProjectName) -> Result<()> {
+    // gc only keeps blobs referenced by registered projects
+    let resources = fs::canonicalize(project_name.to_resources())?;
+    let mut projects = read_projects(cache)?;
+    if projects.insert(resources) {
+        write_atomic(&cache.join(PROJECTS_NAME), &serde_json::to_vec_pretty(&projects)?)?;
+    }
+    Ok(())
+}
+
+/// Saves an account in a storage format, json files decode the data with the configured discriminator strategies
+pub fn save_account(project_name: &ProjectName, storage: Storage, discriminators: Option<&DiscriminatorStrategies>, account: &AccountSchema) -> Result<PathBuf> {
+    let path = match storage {
+        Storage::Bincode => {
+            let path = account_path(project_name, &account.pubkey, BINCODE_EXTENSION);
+            fs::write(&path, bincode::serialize(account)?)?;
+            path
+        },
+        Storage::Cache => {
+            let cache = cache_dir()?;
+            let pointer = CachePointer {
+                pubkey: account.pubkey,
+                network: account.network.clone(),
+                lamports: account.lamports,
+                owner: account.owner,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data_len: account.data.len(),
+                data_hash: write_blob(&cache, &account.data)?,
+            };
+            let path = account_path(project_name, &account.pubkey, CACHE_POINTER_EXTENSION);
+            fs::write(&path, serde_json::to_vec_pretty(&pointer)?)?;
+            register_project(&cache, project_name)?;
+            path
+        },
+        Storage::Json => {
+            let file = AccountFile {
+                pubkey: account.pubkey,
+                network: account.network.clone(),
+                lamports: account.lamports,
+                owner: account.owner,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                decoded: decode_account(project_name, discriminators, account),
+                data_len: account.data.len(),
+                data: to_hex_lines(&account.data),
+            };
+            let path = account_path(project_name, &account.pubkey, JSON_EXTENSION);
+            let mut json = serde_json::to_vec_pretty(&file)?;
+            json.push(b'\n');
+            fs::write(&path, json)?;
+            path
+        },
+    };
+
+    // Only one format is kept per account, so reads never find stale data
+    for extension in [BINCODE_EXTENSION, CACHE_POINTER_EXTENSION, JSON_EXTENSION] {
+        let other = account_path(project_name, &account.pubkey, extension);
+        if other != path && other.exists() {
+            fs::remove_file(other)?;
+        }
+    }
+    Ok(path)
+}
+
+pub fn read_account(project_name: &ProjectName, pubkey: &Pubkey) -> Result<AccountSchema> {
+    read_account_from(Path::new(&project_name.to_resources()), pubkey)
+}
+
+/// Reads an account from any resources directory, eg. of an included config
+pub fn read_account_from(resources: &Path, pubkey: &Pubkey) -> Result<AccountSchema> {
+    let pointer_path = resources.join(format!("{}.{}", pubkey, CACHE_POINTER_EXTENSION));
+    if pointer_path.exists() {
+        let pointer: CachePointer = serde_json::from_slice(&fs::read(&pointer_path)?)
+            .map_err(|e| anyhow!("Invalid cache pointer {}: {}", pointer_path.display(), e))?;
+        let data = read_blob(&cache_dir()?, &pointer.data_hash)?;
+        if data.len() != pointer.data_len {
+            return Err(anyhow!("Data length of {} doesn't match its cache blob", pubkey))
+        }
+        return Ok(AccountSchema {
+            pubkey: pointer.pubkey,
+            network: pointer.network,
+            lamports: pointer.lamports,
+            data,
+            owner: pointer.owner,
+            executable: pointer.executable,
+            rent_epoch: pointer.rent_epoch,
+        })
+    }
+    let json_path = resources.join(format!("{}.{}", pubkey, JSON_EXTENSION));
+    if json_path.exists() {
+        let file: AccountFile = serde_json::from_slice(&fs::read(&json_path)?)
+            .map_err(|e| anyhow!("Invalid account file {}: {}", json_path.display(), e))?;
+        let data = from_hex_lines(&file.data)?;
+        if data.len() != file.data_len {
+            return Err(anyhow!("Data length of {} doesn't match data_len in {}", pubkey, json_path.display()))
+        }
+        return Ok(AccountSchema {
+            pubkey: file.pubkey,
+            network: file.network,
+            lamports: file.lamports,
+            data,
+            owner: file.owner,
+            executable: file.executable,
+            rent_epoch: file.rent_epoch,
+        })
+    }
+    Ok(bincode::deserialize(&fs::read(resources.join(format!("{}.{}", pubkey, BINCODE_EXTENSION)))?)?)
+}
+
+/// Pristine copies are always bincode, in their own directory so they're never read as the current account
+fn pristine_path(resources: &Path, pubkey: &Pubkey) -> PathBuf {
+    resources.join(PRISTINE_DIR).join(format!("{}.{}", pubkey, BINCODE_EXTENSION))
+}
+
+/// Saves an account as it was cloned, imported or created, before any overrides, so they can be reverted
+pub fn save_pristine(project_name: &ProjectName, account: &AccountSchema) -> Result<()> {
+    write_atomic(&pristine_path(Path::new(&project_name.to_resources()), &account.pubkey), &bincode::serialize(account)?)
+}
+
+pub fn read_pristine(project_name: &ProjectName, pubkey: &Pubkey) -> Result<Option<AccountSchema>> {
+    read_pristine_from(Path::new(&project_name.to_resources()), pubkey)
+}
+
+/// Reads the pristine copy of an account from a resources directory, None for accounts saved by older versions
+pub fn read_pristine_from(resources: &Path, pubkey: &Pubkey) -> Result<Option<AccountSchema>> {
+    match fs::read(pristine_path(resources, pubkey)) {
+        Ok(data) => Ok(Some(bincode::deserialize(&data)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether a file of a resources directory is a pointer to the shared cache
+pub fn is_cache_pointer(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.ends_with(&format!(".{}", CACHE_POINTER_EXTENSION)))
+}
+
+pub fn pointer_pubkey(path: &Path) -> Result<Pubkey> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).ok_or(anyhow!("Invalid path {}", path.display()))?;
+    Ok(Pubkey::from_str(file_name.trim_end_matches(&format!(".{}", CACHE_POINTER_EXTENSION)))?)
+}
+
+/// Removes all cache blobs not referenced by a registered project, returning the removed blobs and bytes
+pub fn gc(dry_run: bool) -> Result<(usize, u64)> {
+    let cache = cache_dir()?;
+
+    // Projects that were deleted are dropped from the registry
+    let projects: BTreeSet<PathBuf> = read_projects(&cache)?.into_iter().filter(|project| project.exists()).collect();
+    let mut referenced: BTreeSet<String> = BTreeSet::new();
+    for project in projects.iter() {
+        for entry in fs::read_dir(project)? {
+            let path = entry?.path();
+            if is_cache_pointer(&path) {
+                let pointer: CachePointer = serde_json::from_slice(&fs::read(&path)?)
+                    .map_err(|e| anyhow!("Invalid cache pointer {}: {}", path.display(), e))?;
+                referenced.insert(pointer.data_hash);
+            }
+        }
+    }
+
+    let (mut removed, mut removed_bytes) = (0, 0);
+    let blobs = cache.join("blobs");
+    if blobs.exists() {
+        for prefix in fs::read_dir(&blobs)? {
+            for blob in fs::read_dir(prefix?.path())? {
+                let blob = blob?;
+                if !referenced.contains(&blob.file_name().to_string_lossy().to_string()) {
+                    removed += 1;
+                    removed_bytes += blob.metadata()?.len();
+                    if !dry_run {
+                        fs::remove_file(blob.path())?;
+                    }
+                }
+            }
+        }
+    }
+    if !dry_run {
+        write_atomic(&cache.join(PROJECTS_NAME), &serde_json::to_vec_pretty(&projects)?)?;
+    }
+    Ok((removed, removed_bytes))
+}
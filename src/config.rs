@@ -1,29 +1,221 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use solana_sdk::pubkey::Pubkey;
-use std::{collections::HashSet, path::Path, str::FromStr};
+use serde_json::{json, Value};
+use solana_sdk::{bpf_loader_upgradeable, pubkey::Pubkey};
+use std::{collections::{BTreeMap, HashSet}, path::Path, str::FromStr};
 
 use crate::{
-    common::{helpers, project_name::ProjectName, AccountSchema, Network},
-    context::{Override, Valid8Context},
+    common::{compose::{self, ComposeSource, MergePolicy}, lockfile::{DriftPolicy, Lockfile}, helpers, idl::DiscriminatorStrategy, project_name::ProjectName, storage::Storage, AccountSchema, Network},
+    context::{CreatedAccount, EditField, Override, Valid8Context},
+    serialization::b58,
 };
 use serde::{Deserialize, Serialize};
 
+/// Version of the valid8.json schema written by this build, older configs are migrated when opened
+pub const CONFIG_VERSION: u32 = 4;
+
+/// Configs without a version field, with `[pubkey, network]` tuple entries
+const LEGACY_CONFIG_VERSION: u32 = 1;
+
+fn legacy_config_version() -> u32 {
+    LEGACY_CONFIG_VERSION
+}
+
+/// User metadata of an account or program, only stored in the config
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct EntryMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Slot the account was last fetched at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+}
+
+/// An account or program of the config
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    #[serde(with = "b58")]
+    pub pubkey: Pubkey,
+    pub network: Network,
+    #[serde(flatten)]
+    pub metadata: EntryMetadata,
+}
+
+impl ConfigEntry {
+    pub fn new(pubkey: Pubkey, network: Network) -> Self {
+        Self { pubkey, network, metadata: EntryMetadata::default() }
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct ConfigJson {
+    #[serde(default = "legacy_config_version")]
+    pub version: u32,
     pub project_name: ProjectName,
     pub networks: HashSet<Network>,
-    pub programs: Vec<(String, Network)>,
-    pub accounts: Vec<(String, Network)>,
+    pub programs: Vec<ConfigEntry>,
+    pub accounts: Vec<ConfigEntry>,
     pub overrides: Option<Vec<Override>>,
     pub idls: Vec<String>,
-    pub compose: Option<String>
+    pub local_idls: Option<BTreeMap<String, String>>,
+    pub discriminators: Option<BTreeMap<String, DiscriminatorStrategy>>,
+    pub created: Option<Vec<CreatedAccount>>,
+    pub storage: Option<Storage>,
+    /// Included configs, paths are relative to this config
+    pub compose: Option<Vec<ComposeSource>>,
+    pub compose_policy: Option<MergePolicy>,
+    pub on_drift: Option<DriftPolicy>,
 }
 
 
 #[allow(unused_assignments)]
 impl ConfigJson {
-    pub fn to_context(&self) -> Result<Valid8Context> {
+    /// Parses a config, migrating older versions to `CONFIG_VERSION`, returns the config and the version it was stored as
+    pub fn parse(buf: &[u8]) -> Result<(Self, u32)> {
+        let mut value: Value = serde_json::from_slice(buf).map_err(|e| anyhow!("Invalid json: {}", e))?;
+        let version = match value.get("version") {
+            Some(version) => version.as_u64().and_then(|v| u32::try_from(v).ok()).ok_or(anyhow!("version: expected a number, found {}", version))?,
+            None => LEGACY_CONFIG_VERSION,
+        };
+        if version > CONFIG_VERSION {
+            return Err(anyhow!("Config version {} is newer than version {} supported by valid8 {}, please update valid8", version, CONFIG_VERSION, env!("CARGO_PKG_VERSION")))
+        }
+        Self::migrate(&mut value, version);
+
+        // Errors point at the invalid field, eg. `accounts[2].network`
+        let config = serde_path_to_error::deserialize(value).map_err(|e| match e.path().to_string().as_str() {
+            "." => anyhow!("{}", e.inner()),
+            path => anyhow!("{}: {}", path, e.inner()),
+        })?;
+        Ok((config, version))
+    }
+
+    fn migrate(value: &mut Value, version: u32) {
+        // Version 1 stored accounts and programs as `[pubkey, network]` tuples
+        if version < 2 {
+            for key in ["programs", "accounts"] {
+                for entry in value.get_mut(key).and_then(Value::as_array_mut).into_iter().flatten() {
+                    let migrated = match entry.as_array().map(Vec::as_slice) {
+                        Some([pubkey, network]) => json!({ "pubkey": pubkey, "network": network }),
+                        _ => continue,
+                    };
+                    *entry = migrated;
+                }
+            }
+        }
+        // Version 2 included a single config with a `compose` string
+        if version < 3 {
+            if let Some(compose) = value.get_mut("compose").filter(|compose| compose.is_string()) {
+                *compose = json!([compose.take()]);
+            }
+        }
+        // Version 3 also used `local` for imported accounts and programs added from .so files, which were never fetched
+        // and so have no slot, accounts cloned from a local validator always have one
+        if version < 4 {
+            for key in ["programs", "accounts"] {
+                for entry in value.get_mut(key).and_then(Value::as_array_mut).into_iter().flatten() {
+                    if entry.get("network").and_then(Value::as_str) == Some("local") && entry.get("slot").is_none() {
+                        entry["network"] = json!(Network::Imported);
+                    }
+                }
+            }
+        }
+        if let Some(config) = value.as_object_mut() {
+            config.insert("version".into(), json!(CONFIG_VERSION));
+        }
+    }
+
+    /// Checks the references between config entries and files, returns a description of each problem
+    pub fn validate(&self, offline: bool) -> Vec<String> {
+        let mut problems = vec![];
+        let mut listed: HashSet<Pubkey> = HashSet::new();
+        let mut labels: HashSet<&str> = HashSet::new();
+        for (key, entries) in [("programs", &self.programs), ("accounts", &self.accounts)] {
+            for (i, entry) in entries.iter().enumerate() {
+                if !listed.insert(entry.pubkey) {
+                    problems.push(format!("{}[{}]: {} is listed more than once", key, i, entry.pubkey));
+                }
+                // Any unknown network name is read as a custom RPC url, so typos only show up here
+                if let Network::Custom(url) = &entry.network {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        problems.push(format!("{}[{}].network: {} is not mainnet, devnet, local or an http(s) RPC url", key, i, url));
+                    }
+                }
+                if let Some(label) = entry.metadata.label.as_deref() {
+                    if label.trim().is_empty() {
+                        problems.push(format!("{}[{}].label: empty label", key, i));
+                    } else if Pubkey::from_str(label).is_ok() {
+                        problems.push(format!("{}[{}].label: {} would be read as a pubkey", key, i, label));
+                    } else if !labels.insert(label) {
+                        problems.push(format!("{}[{}].label: {} is used more than once", key, i, label));
+                    }
+                }
+                if let Some(j) = entry.metadata.tags.iter().position(|tag| tag.trim().is_empty() || tag.contains(',')) {
+                    problems.push(format!("{}[{}].tags[{}]: tags must be non empty and without commas", key, i, j));
+                }
+            }
+        }
+        for (i, created) in self.created.iter().flatten().enumerate() {
+            if !listed.insert(created.pubkey) {
+                problems.push(format!("created[{}]: {} is also a cloned account", i, created.pubkey));
+            }
+        }
+
+        let has_idl = |program_id: &str| self.idls.iter().any(|idl| idl == program_id)
+            || self.local_idls.as_ref().is_some_and(|local_idls| local_idls.contains_key(program_id));
+        for (i, idl) in self.idls.iter().enumerate() {
+            if Pubkey::from_str(idl).is_err() {
+                problems.push(format!("idls[{}]: invalid program id {}", i, idl));
+            }
+        }
+        for (program_id, path) in self.local_idls.iter().flatten() {
+            if Pubkey::from_str(program_id).is_err() {
+                problems.push(format!("local_idls.{}: invalid program id", program_id));
+            }
+            if !Path::new(path).exists() {
+                problems.push(format!("local_idls.{}: IDL file {} not found", program_id, path));
+            }
+        }
+        for program_id in self.discriminators.iter().flatten().map(|(program_id, _)| program_id) {
+            if Pubkey::from_str(program_id).is_err() {
+                problems.push(format!("discriminators.{}: invalid program id", program_id));
+            }
+        }
+        for (i, created) in self.created.iter().flatten().enumerate() {
+            if !has_idl(&created.program_id.to_string()) {
+                problems.push(format!("created[{}].program_id: no IDL for {}", i, created.program_id));
+            }
+        }
+        for (i, over) in self.overrides.iter().flatten().enumerate() {
+            if !listed.contains(&over.pubkey) {
+                problems.push(format!("overrides[{}].pubkey: {} is not an account or program of the config", i, over.pubkey));
+            }
+            for (j, edit_field) in over.edit_fields.iter().enumerate() {
+                match edit_field {
+                    EditField::IdlField { program_id, .. } if !has_idl(&program_id.to_string()) => {
+                        problems.push(format!("overrides[{}].edit_fields[{}].program_id: no IDL for {}", i, j, program_id));
+                    },
+                    EditField::Data(_) => problems.push(format!("overrides[{}].edit_fields[{}]: raw data edits aren't supported", i, j)),
+                    EditField::Relocate { to, .. } if listed.contains(to) => {
+                        problems.push(format!("overrides[{}].edit_fields[{}].to: {} is already an account or program of the config", i, j, to));
+                    },
+                    _ => {},
+                }
+            }
+        }
+        let resolved = Lockfile::read(&self.project_name)
+            .and_then(|lock| compose::resolve(Path::new(&self.project_name.to_config()), self, &lock, false, offline));
+        if let Err(e) = resolved {
+            problems.push(format!("compose: {}", e));
+        }
+        problems
+    }
+
+    /// Builds a context with the accounts and programs of the config, cloning them from their networks unless they're imported.
+    /// Offline contexts fail instead of cloning
+    pub fn to_context(&self, offline: bool) -> Result<Valid8Context> {
         let mut account_counter = 0;
         let mut new_context = Valid8Context {
             project_name: self.project_name.clone(),
@@ -32,7 +224,15 @@ impl ConfigJson {
             accounts: vec![],
             overrides: self.overrides.clone(),
             idls: self.idls.clone(),
+            local_idls: self.local_idls.clone(),
+            discriminators: self.discriminators.clone(),
+            created: self.created.clone(),
+            storage: self.storage,
             compose: self.compose.clone(),
+            compose_policy: self.compose_policy,
+            on_drift: self.on_drift,
+            metadata: self.metadata(),
+            offline,
         };
         Valid8Context::create_resources_dir(&new_context.project_name)?;
 
@@ -40,86 +240,295 @@ impl ConfigJson {
             .accounts
             .clone()
             .into_par_iter()
-            .map(|(pubkey_string, network)| {
-                let account = helpers::fetch_account(&network, &Pubkey::from_str(&pubkey_string)?)?;
-                helpers::save_account_to_disc(&self.project_name, &account)?;
-                Ok(account)
+            .map(|ConfigEntry { pubkey, network, .. }| {
+                // Imported accounts only exist on disc
+                if network == Network::Imported {
+                    let account = helpers::read_account_from_disc(&self.project_name, &pubkey.to_string())
+                        .map_err(|e| anyhow!("Imported account {} not found in {}, import it again: {}", pubkey, self.project_name.to_resources(), e))?;
+                    return Ok((AccountSchema { network, ..account }, None))
+                }
+                let (account, slot) = helpers::fetch_account_with_slot(&network, &pubkey, offline)?;
+                Ok((account, Some(slot)))
             })
-            .collect::<Result<Vec<AccountSchema>>>()?;
-        account_counter = accounts.len();
-        new_context.accounts = accounts;
+            .collect::<Result<Vec<(AccountSchema, Option<u64>)>>>()?;
 
         let programs = self
             .programs
             .clone()
             .into_par_iter()
-            .map(|(pubkey_string, network)| {
-                let program_account =
-                    helpers::fetch_account(&network, &Pubkey::from_str(&pubkey_string)?)?;
+            .map(|ConfigEntry { pubkey, network, .. }| {
+                // Imported programs are added from .so files and only exist on disc
+                if network == Network::Imported {
+                    let missing = |pubkey: &Pubkey, e: anyhow::Error| anyhow!("Imported program account {} not found in {}, add it again: {}", pubkey, self.project_name.to_resources(), e);
+                    let program_account = helpers::read_account_from_disc(&self.project_name, &pubkey.to_string())
+                        .map_err(|e| missing(&pubkey, e))
+                        .map(|program_account| AccountSchema { network: Network::Imported, ..program_account })?;
+                    let program_data = match program_account.owner == bpf_loader_upgradeable::id() {
+                        true => {
+                            let program_data_address = program_account.get_program_executable_data_address()?;
+                            let program_data = helpers::read_account_from_disc(&self.project_name, &program_data_address.to_string())
+                                .map_err(|e| missing(&program_data_address, e))?;
+                            Some(AccountSchema { network: Network::Imported, ..program_data })
+                        },
+                        false => None,
+                    };
+                    let program_idl = helpers::clone_idl(&new_context, &program_account);
+                    return Ok((program_account, program_data, program_idl, None))
+                }
+                let (program_account, slot) = helpers::fetch_account_with_slot(&network, &pubkey, offline)?;
                 let program_data = helpers::clone_program_data(&new_context, &program_account)?;
-                helpers::save_account_to_disc(&self.project_name, &program_account)?;
-                helpers::save_account_to_disc(&self.project_name, &program_data)?;
-                let program_idl = helpers::clone_idl(&program_account);
-                Ok((program_account, program_data, program_idl))
+                let program_idl = helpers::clone_idl(&new_context, &program_account);
+                Ok((program_account, Some(program_data), program_idl, Some(slot)))
             })
-            .collect::<Result<Vec<(AccountSchema, AccountSchema, Result<_>)>>>()?;
+            .collect::<Result<Vec<(AccountSchema, Option<AccountSchema>, Result<_>, Option<u64>)>>>()?;
+
+        // Fetched accounts are checked against the lockfile before anything is saved
+        let fetched: Vec<(&AccountSchema, u64)> = accounts
+            .iter()
+            .filter_map(|(account, slot)| slot.map(|slot| (account, slot)))
+            .chain(programs.iter().filter_map(|(program, program_data, _, slot)| {
+                slot.map(|slot| [Some((program, slot)), program_data.as_ref().map(|program_data| (program_data, slot))])
+            }).flatten().flatten())
+            .collect();
+        let mut lock = Lockfile::read(&self.project_name)?;
+        let drifted = lock.drift(&fetched);
+        if !drifted.is_empty() {
+            let report = format!(
+                "{} accounts changed since they were locked in {}:\n    {}\nRun `valid8 update` to accept the changes",
+                drifted.len(), self.project_name.to_lockfile(), drifted.join("\n    ")
+            );
+            match self.on_drift.unwrap_or_default() {
+                DriftPolicy::Error => return Err(anyhow!(report)),
+                DriftPolicy::Warn => eprintln!("⚠️  {}", report),
+            }
+        }
+        for (account, _) in fetched.iter() {
+            helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), account)?;
+        }
+        if lock.lock_new(&fetched) {
+            lock.save(&self.project_name)?;
+        }
+
+        account_counter = accounts.len();
+        for (account, slot) in accounts {
+            if slot.is_some() {
+                new_context.metadata.entry(account.pubkey).or_default().slot = slot;
+            }
+            new_context.accounts.push(account);
+        }
 
-        programs.into_iter().for_each(|(program, program_data, program_idl)| {
+        programs.into_iter().for_each(|(program, program_data, program_idl, slot)| {
+            if slot.is_some() {
+                new_context.metadata.entry(program.pubkey).or_default().slot = slot;
+            }
             new_context.programs.push(program.clone());
-            new_context.accounts.push(program_data);
-            if program_idl.is_ok() {
+            // Program data accounts are also in the accounts list of the config
+            if let Some(program_data) = program_data.filter(|program_data| !new_context.has_account(&program_data.pubkey)) {
+                new_context.accounts.push(program_data);
+            }
+            if program_idl.is_ok() && !new_context.idls.contains(&program.pubkey.to_string()) {
                 new_context.idls.push(program.pubkey.to_string())
             }
             account_counter+=2;
         });
+
+        // Created accounts are rebuilt from their IDL type and values, after the IDLs are cloned
+        for created in self.created.iter().flatten() {
+            let account = new_context.build_created_account(created)?;
+            helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+            new_context.accounts.push(account);
+            account_counter+=1;
+        }
         new_context.apply_overrides()?;
 
         Ok(new_context)
     }
 
+    pub fn storage(&self) -> Storage {
+        self.storage.unwrap_or_default()
+    }
+
+    /// Metadata of all entries that have any
+    pub fn metadata(&self) -> BTreeMap<Pubkey, EntryMetadata> {
+        self.programs
+            .iter()
+            .chain(self.accounts.iter())
+            .filter(|entry| entry.metadata != EntryMetadata::default())
+            .map(|entry| (entry.pubkey, entry.metadata.clone()))
+            .collect()
+    }
+
+    /// Keeps only the accounts and programs that have any of the tags, the program data accounts of the kept programs,
+    /// and their overrides. Created accounts can't have tags, they are removed
+    pub fn retain_tagged(&mut self, tags: &[String]) {
+        let tagged = |entry: &ConfigEntry| entry.metadata.tags.iter().any(|tag| tags.contains(tag));
+        self.programs.retain(tagged);
+        let program_data: Vec<Pubkey> = self.programs
+            .iter()
+            .map(|program| Pubkey::find_program_address(&[program.pubkey.as_ref()], &bpf_loader_upgradeable::id()).0)
+            .collect();
+        self.accounts.retain(|entry| tagged(entry) || program_data.contains(&entry.pubkey));
+        self.created = None;
+        let kept: HashSet<Pubkey> = self.programs.iter().chain(self.accounts.iter()).map(|entry| entry.pubkey).collect();
+        if let Some(overrides) = self.overrides.as_mut() {
+            overrides.retain(|over| kept.contains(&over.pubkey));
+        }
+    }
+
     pub fn is_installed(&self) -> bool {
-        let mut ret = false;
-        if Path::new(&self.project_name.to_resources()).exists() && self
-                .accounts
-                .clone()
-                .into_par_iter()
-                .all(|(pubkey, _network)| {
-                    helpers::read_account_from_disc(&self.project_name, &pubkey).is_ok()
-                }) {
-            ret = true;
-        }
-        ret
+        Path::new(&self.project_name.to_resources()).exists() && self.missing_accounts().is_empty()
+    }
+
+    /// Lists the accounts and programs of this config that are not saved in the resources directory
+    pub fn missing_accounts(&self) -> Vec<(String, Network)> {
+        self.programs
+            .iter()
+            .chain(self.accounts.iter())
+            .map(|entry| (entry.pubkey.to_string(), entry.network.clone()))
+            .chain(self.created.iter().flatten().map(|created| (created.pubkey.to_string(), Network::Imported)))
+            .collect::<Vec<(String, Network)>>()
+            .into_par_iter()
+            .filter(|(pubkey, _network)| helpers::read_account_from_disc(&self.project_name, pubkey).is_err())
+            .collect()
     }
 }
 
 impl From<Valid8Context> for ConfigJson {
     fn from(value: Valid8Context) -> Self {
-        let programs: Vec<(String, Network)> = value
+        let entry = |a_s: &AccountSchema| ConfigEntry {
+            pubkey: a_s.pubkey,
+            network: a_s.network.clone(),
+            metadata: value.metadata.get(&a_s.pubkey).cloned().unwrap_or_default(),
+        };
+
+        let programs: Vec<ConfigEntry> = value
             .programs
             .iter()
             .map(|a_s| {
-                let _ = helpers::save_account_to_disc(&value.project_name, a_s);
-                (a_s.pubkey.to_string(), a_s.network.clone())
+                let _ = helpers::save_account_to_disc(&value.project_name, value.storage(), value.discriminators.as_ref(), a_s);
+                entry(a_s)
             })
             .collect();
 
-        let accounts: Vec<(String, Network)> = value
+        let accounts: Vec<ConfigEntry> = value
             .accounts
             .iter()
-            .map(|a_s| {
-                let _ = helpers::save_account_to_disc(&value.project_name, a_s);
-                (a_s.pubkey.to_string(), a_s.network.clone())
+            .filter_map(|a_s| {
+                let _ = helpers::save_account_to_disc(&value.project_name, value.storage(), value.discriminators.as_ref(), a_s);
+                // Created accounts are stored with their definition instead
+                match value.created.iter().flatten().any(|created| created.pubkey == a_s.pubkey) {
+                    true => None,
+                    false => Some(entry(a_s)),
+                }
             })
             .collect();
 
         Self {
+            version: CONFIG_VERSION,
             project_name: value.project_name,
             networks: value.networks,
             programs,
             accounts,
             overrides: value.overrides,
             idls: value.idls,
+            local_idls: value.local_idls,
+            discriminators: value.discriminators,
+            created: value.created,
+            storage: value.storage,
             compose: value.compose,
+            compose_policy: value.compose_policy,
+            on_drift: value.on_drift,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: Value) -> Result<(ConfigJson, u32)> {
+        ConfigJson::parse(&serde_json::to_vec(&value).unwrap())
+    }
+
+    #[test]
+    fn migrates_v1_tuples_to_current_version() {
+        let (program, account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (config, version) = parse(json!({
+            "project_name": { "name": "valid8" },
+            "networks": ["mainnet"],
+            "programs": [[program.to_string(), "mainnet"]],
+            "accounts": [[account.to_string(), "local"]],
+            "idls": [],
+            "compose": "base.json",
+        })).unwrap();
+
+        assert_eq!(version, 1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.programs, vec![ConfigEntry::new(program, Network::Mainnet)]);
+        assert_eq!(config.accounts, vec![ConfigEntry::new(account, Network::Imported)]);
+        assert_eq!(config.compose, Some(vec![ComposeSource::Path("base.json".into())]));
+    }
+
+    #[test]
+    fn migrates_v2_compose_string_to_list() {
+        let (config, version) = parse(json!({
+            "version": 2,
+            "project_name": { "name": "valid8" },
+            "networks": [],
+            "programs": [],
+            "accounts": [],
+            "idls": [],
+            "compose": "../shared/valid8.json",
+        })).unwrap();
+
+        assert_eq!(version, 2);
+        assert_eq!(config.compose, Some(vec![ComposeSource::Path("../shared/valid8.json".into())]));
+    }
+
+    #[test]
+    fn migrates_v3_local_entries_without_slot_to_imported() {
+        let (imported, cloned, program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (config, version) = parse(json!({
+            "version": 3,
+            "project_name": { "name": "valid8" },
+            "networks": ["local"],
+            "programs": [{ "pubkey": program.to_string(), "network": "local" }],
+            "accounts": [
+                { "pubkey": imported.to_string(), "network": "local", "label": "fixture" },
+                { "pubkey": cloned.to_string(), "network": "local", "slot": 42 },
+            ],
+            "idls": [],
+            "compose": ["base.json"],
+        })).unwrap();
+
+        assert_eq!(version, 3);
+        assert_eq!(config.programs[0].network, Network::Imported);
+        assert_eq!(config.accounts[0].network, Network::Imported);
+        assert_eq!(config.accounts[0].metadata.label.as_deref(), Some("fixture"));
+        assert_eq!(config.accounts[1].network, Network::Local);
+        assert_eq!(config.accounts[1].metadata.slot, Some(42));
+        assert_eq!(config.compose, Some(vec![ComposeSource::Path("base.json".into())]));
+    }
+
+    #[test]
+    fn keeps_current_version_as_is() {
+        let account = Pubkey::new_unique();
+        let (config, version) = parse(json!({
+            "version": CONFIG_VERSION,
+            "project_name": { "name": "valid8" },
+            "networks": [],
+            "programs": [],
+            "accounts": [{ "pubkey": account.to_string(), "network": "local" }],
+            "idls": [],
+        })).unwrap();
+
+        assert_eq!(version, CONFIG_VERSION);
+        assert_eq!(config.accounts, vec![ConfigEntry::new(account, Network::Local)]);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let error = parse(json!({ "version": CONFIG_VERSION + 1 })).unwrap_err().to_string();
+        assert!(error.contains("please update valid8"), "{}", error);
+    }
+}
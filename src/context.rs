@@ -1,7 +1,5 @@
-use std::{collections::{HashMap, HashSet}, fs::{create_dir_all, File}, io::{Read, Write}, path::Path, str::FromStr};
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs::{create_dir_all, File}, io::{Read, Write}, path::{Path, PathBuf}, str::FromStr};
 use anyhow::Result;
-use dialoguer::{Input, Select};
-use rayon::iter::SkipAnyWhile;
 use serde::{Serialize, Deserialize};
 use anyhow::anyhow;
 use serde_json::Value;
@@ -13,14 +11,16 @@ use solana_ledger::{
 use solana_runtime::genesis_utils::create_genesis_config_with_leader_ex;
 
 use solana_sdk::{
-    account::AccountSharedData, account_utils::StateMut, bpf_loader_upgradeable::UpgradeableLoaderState, epoch_schedule::EpochSchedule, fee_calculator::FeeRateGovernor, native_token::sol_to_lamports, program_pack::Pack, pubkey::Pubkey, rent::Rent, signature::{write_keypair_file, Keypair}, signer::Signer, system_program
+    account::AccountSharedData, account_utils::StateMut, bpf_loader, bpf_loader_upgradeable::{self, UpgradeableLoaderState}, epoch_schedule::EpochSchedule, fee_calculator::FeeRateGovernor, genesis_config::GenesisConfig, native_token::sol_to_lamports, program_pack::Pack, pubkey::Pubkey, rent::Rent, signature::{write_keypair_file, Keypair}, signer::Signer, system_program
 };
 
 use spl_token::state::Account as TokenAccount;
 
+use anchor_lang::anchor_syn::idl::types::{Idl, IdlTypeDefinition};
+
 use crate::{common::{
-        helpers, idl::{self, unpack_idl_account}, project_name::ProjectName, AccountSchema, Network
-    }, config::ConfigJson, serialization::b58
+        compose::{self, ComposeReport, ComposeSource, MergePolicy}, diff::{self, AccountDiff, OwnerIdl}, helpers, lockfile::{DriftPolicy, LockedAccount, Lockfile}, idl::{self, DiscriminatorStrategy, FieldValue, IdlAccountField, Serialization}, pda, project_name::ProjectName, storage::{self, Storage}, AccountSchema, Network
+    }, config::{ConfigJson, EntryMetadata}, serialization::b58
 };
 //const MAX_GENESIS_ARCHIVE_UNPACKED_SIZE: u64 = 10 * 1024 * 1024; // 10 MiB from testvalidator source is not enough 
 
@@ -45,7 +45,18 @@ pub struct Valid8Context {
     pub accounts: Vec<AccountSchema>,
     pub overrides: Option<Vec<Override>>,
     pub idls: Vec<String>,
-    pub compose: Option<String>,
+    pub local_idls: Option<BTreeMap<String, String>>,
+    pub discriminators: Option<BTreeMap<String, DiscriminatorStrategy>>,
+    pub created: Option<Vec<CreatedAccount>>,
+    pub storage: Option<Storage>,
+    pub compose: Option<Vec<ComposeSource>>,
+    pub compose_policy: Option<MergePolicy>,
+    pub on_drift: Option<DriftPolicy>,
+    /// Labels, tags and fetch slots of accounts and programs
+    pub metadata: BTreeMap<Pubkey, EntryMetadata>,
+    /// Fails RPC calls and remote compose fetches instead of connecting, it isn't stored in the config
+    #[serde(skip)]
+    pub offline: bool,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -61,6 +72,18 @@ impl Override {
     }
 }
 
+/// An account that doesn't exist on any network, built from an IDL account type and json values
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreatedAccount {
+    #[serde(with = "b58")]
+    pub pubkey: Pubkey,
+    #[serde(with = "b58")]
+    pub program_id: Pubkey,
+    pub account_type: String,
+    pub values: Value,
+    pub space: Option<usize>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum EditField{
     #[serde(with = "b58")]
@@ -69,57 +92,179 @@ pub enum EditField{
     UpgradeAuthority(Pubkey),
     Lamports(u64),
     Data(Value),
+    #[serde(with = "b58")]
+    TokenOwner(Pubkey),
+    TokenAmount(u64),
+    #[serde(with = "b58")]
+    TokenDelegate(Pubkey),
+    TokenDelegatedAmount(u64),
+    /// A field of an IDL account type, nested fields are named like `pool.mint` or `orders[3].price`
+    IdlField {
+        #[serde(with = "b58")]
+        program_id: Pubkey,
+        account_type: String,
+        field: String,
+        value: String,
+    },
+    /// Moves the account to a new address in ledgers and exports, optionally replacing references
+    /// to the old address in the data and owner of all accounts
+    Relocate {
+        #[serde(with = "b58")]
+        to: Pubkey,
+        #[serde(default)]
+        rewrite_references: bool,
+        /// Also rewrite references in programs and program data, which are skipped as their ELF bytes can contain the address by chance
+        #[serde(default)]
+        rewrite_programs: bool,
+    },
+    // Interactive edits recorded by older versions, the edited data is already saved on disc
     UnpackTokenAccount,
     UnpackPDA,
 }
 
+impl EditField {
+    /// The name of the edited value, IDL fields are prefixed with `data.` like in `valid8 diff`
+    pub fn field_name(&self) -> String {
+        match self {
+            EditField::Owner(_) => "owner".to_string(),
+            EditField::UpgradeAuthority(_) => "program_data.upgrade_authority".to_string(),
+            EditField::Lamports(_) => "lamports".to_string(),
+            EditField::Data(_) => "data".to_string(),
+            EditField::TokenOwner(_) => "token.owner".to_string(),
+            EditField::TokenAmount(_) => "token.amount".to_string(),
+            EditField::TokenDelegate(_) => "token.delegate".to_string(),
+            EditField::TokenDelegatedAmount(_) => "token.delegated_amount".to_string(),
+            EditField::IdlField { field, .. } => format!("data.{}", field),
+            EditField::UnpackTokenAccount => "unpack_token_account".to_string(),
+            EditField::Relocate { .. } => "address".to_string(),
+            EditField::UnpackPDA => "unpack_pda".to_string(),
+        }
+    }
+
+    /// Whether both edits set the same value of an account, so the later one replaces the earlier one
+    pub fn same_target(&self, other: &EditField) -> bool {
+        match (self, other) {
+            (EditField::IdlField { account_type, field, .. }, EditField::IdlField { account_type: other_type, field: other_field, .. }) => {
+                account_type == other_type && field == other_field
+            },
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// An override with the value of the pristine account and the current value
+#[derive(Debug, Clone)]
+pub struct OverrideState {
+    pub pubkey: Pubkey,
+    pub edit_field: EditField,
+    /// None without a pristine copy, eg. accounts saved by older versions
+    pub original: Option<String>,
+    pub current: Option<String>,
+}
+
+/// The overrides added when rebasing a program on a new program id
+#[derive(Debug, Clone, Default)]
+pub struct RebaseReport {
+    /// Accounts that are owned by the new program id, by the relocation of the program or an owner override when it isn't relocated
+    pub owners: Vec<Pubkey>,
+    /// PDAs with their address derived from the new program id
+    pub relocated: Vec<(Pubkey, Pubkey)>,
+    /// Owned accounts without seeds found in the IDL, they keep their address
+    pub not_derived: Vec<Pubkey>,
+    pub program_relocated: bool,
+}
+
+/// An account unpacked with its program IDL
+#[derive(Debug, Clone)]
+pub struct UnpackedIdlAccount {
+    pub discriminator_len: usize,
+    pub idl_type_def: IdlTypeDefinition,
+    pub fields: Vec<IdlAccountField>,
+}
+
+/// A genesis config with all accounts and programs of a context, and the keypairs it was created with
+pub struct Valid8Genesis {
+    pub genesis_config: GenesisConfig,
+    pub validator_identity: Keypair,
+    pub validator_vote_account: Keypair,
+    pub validator_stake_account: Keypair,
+    pub faucet_keypair: Keypair,
+}
+
 impl From<ConfigJson> for Valid8Context {
     fn from(value: ConfigJson) -> Self {
 
-        // Try to read accounts from disc, or return with default empty vector
+        // Try to read accounts from disc, or return with default empty vector.
+        // The network of the config is kept, files saved by older versions have `local` for imported accounts
+        let read = |pubkey: &Pubkey, network: &Network| helpers::read_account_from_disc(&value.project_name, &pubkey.to_string())
+            .map(|account| AccountSchema { network: network.clone(), ..account });
         let programs = value.programs.iter()
-            .map(|(pubkey, _)| helpers::read_account_from_disc(&value.project_name, pubkey))
+            .map(|entry| read(&entry.pubkey, &entry.network))
             .collect::<Result<Vec<AccountSchema>>>()
             .unwrap_or_default();
 
         let accounts = value.accounts.iter()
-            .map(|(pubkey, _)| helpers::read_account_from_disc(&value.project_name, pubkey))
+            .map(|entry| read(&entry.pubkey, &entry.network))
+            .chain(value.created.iter().flatten().map(|created| read(&created.pubkey, &Network::Imported)))
             .collect::<Result<Vec<AccountSchema>>>()
             .unwrap_or_default();
         
         Self { 
+            metadata: value.metadata(),
             project_name: value.project_name,
             networks: value.networks,
             programs,
             accounts,
             idls: value.idls,
+            local_idls: value.local_idls,
+            discriminators: value.discriminators,
+            created: value.created,
+            storage: value.storage,
             overrides: value.overrides,
             compose: value.compose,
+            compose_policy: value.compose_policy,
+            on_drift: value.on_drift,
+            offline: false,
         }
     }
 }
 
 impl Valid8Context {
 
-    pub fn init() -> Result<Valid8Context>{
-        let project_name = ProjectName::default();
-        
-        if let Ok((config, installed)) = Self::try_open_config(&project_name) {
-            if !installed {
-                let choice: String = Input::new().with_prompt("Install Accounts to local?[y/n]").interact_text()?;
-
-                match choice.as_str() {
-                    "y" => Ok(config.to_context()?),
-                    "n" | _ => Err(anyhow!("Accounts not installed")),
-                }
+    /// Loads a project config with its accounts and programs read from disc
+    pub fn load(project_name: &ProjectName) -> Result<Valid8Context> {
+        let (config, installed) = Self::try_open_config(project_name)?;
+        if !installed {
+            return Err(anyhow!("Accounts of {} are not installed", project_name.to_config()))
+        }
+        Ok(config.into())
+    }
 
-            } else {
-                println!("{} config found, accounts installed: {}", project_name.to_config(), installed);
-                Ok(config.into())
-            }
-        } else {
-            Self::try_init_config(&project_name)
+    /// Loads a project config from disc only, without any RPC calls, failing with the list of missing accounts
+    pub fn load_offline(project_name: &ProjectName) -> Result<Valid8Context> {
+        let (config, _) = Self::try_open_config(project_name)?;
+        let missing = config.missing_accounts();
+        if !missing.is_empty() {
+            let missing_list = missing
+                .iter()
+                .map(|(pubkey, network)| match helpers::read_account_from_disc(project_name, pubkey) {
+                    // Files that exist but can't be read, eg. corrupted cache blobs
+                    Err(e) if e.downcast_ref::<std::io::Error>().map(|e| e.kind()) != Some(std::io::ErrorKind::NotFound) => {
+                        format!("    {} ({}): {}", pubkey, network, e)
+                    },
+                    _ => format!("    {} ({})", pubkey, network),
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            return Err(anyhow!("{} accounts missing from {} in offline mode:\n{}", missing.len(), project_name.to_resources(), missing_list))
         }
+        Ok(Valid8Context { offline: true, ..config.into() })
+    }
+
+    /// Loads a project config and clones all of its accounts and programs from their networks
+    pub fn install(project_name: &ProjectName) -> Result<Valid8Context> {
+        let (config, _) = Self::try_open_config(project_name)?;
+        config.to_context(false)
     }
 
     pub fn create_resources_dir(project_name: &ProjectName) -> Result<()> {
@@ -157,63 +302,64 @@ impl Valid8Context {
         let mut buf = vec![];
         File::open(Path::new(&project_name.to_config()))
             .and_then(|mut file| file.read_to_end(&mut buf))?;
-        let config: ConfigJson = serde_json::from_slice(&buf)?;
-        println!("Config {:?}", &config);
+        let (config, _) = ConfigJson::parse(&buf).map_err(|e| anyhow!("{}: {}", project_name.to_config(), e))?;
     
         // Convert ConfigJson to Valid8Context, this also tries to read accounts from disc
         let mut installed = true;
         if !&config.is_installed() {
-            println!("Accounts not found in local workspace, please run valid8 install to clone them.");
+            eprintln!("Accounts not found in local workspace, please run valid8 install to clone them.");
             installed = false;
         }
 
         Ok((config, installed))
     }
 
-    pub fn try_compose(self) -> Result<(u8, u32, u32)> {
+    /// Merges all configs included by compose into this one and installs the result,
+    /// conflicts are resolved with `policy`, or the `compose_policy` of the config.
+    /// Remote includes are pinned in the lockfile, `update` fetches their latest version instead.
+    /// With `tags`, only the accounts and programs of included configs that have any of them are merged.
+    pub fn try_compose(self, policy: Option<MergePolicy>, update: bool, tags: &[String]) -> Result<ComposeReport> {
+        let root_path = PathBuf::from(self.project_name.to_config());
+        let policy = policy.or(self.compose_policy).unwrap_or_default();
+        let project_name = self.project_name.clone();
+        let offline = self.offline;
+        let this_config: ConfigJson = self.into();
+        let lock = Lockfile::read(&project_name)?;
+        let (mut included, new_lock) = compose::resolve(&root_path, &this_config, &lock, update, offline)?;
+        if !tags.is_empty() {
+            for composed in included.iter_mut() {
+                composed.config.retain_tagged(tags);
+            }
+        }
 
-        let mut new_config_path = self.compose.clone();
-        let mut this_ctx: ConfigJson = self.into();
-        let mut compose_count = 0;
-        let mut account_count = 0;
-        let mut program_count = 0;
+        let mut merger = compose::Merger::new(this_config, &root_path, policy);
+        for composed in included.iter() {
+            merger.merge(composed);
+        }
+        let (this_config, mut report) = merger.finish()?;
+        report.copied = compose::copy_resources(&this_config, &included, &report.sources)?;
+        // Installing locks the cloned accounts, so the compose sources are locked first
+        if new_lock != lock {
+            new_lock.save(&project_name)?;
+        }
+        let new_context = this_config.to_context(offline)?;
+        new_context.try_save_config()?;
 
-        while let Some(new_config) = new_config_path {
-            compose_count += 1;
+        Ok(report)
+    }
 
-            if compose_count>20{return Err(anyhow!(compose_count))};
-            
-            let (new_ctx, _) = Valid8Context::try_open_config(&ProjectName::from_str(&new_config.replace(".json", ""))?)?;
+    pub fn storage(&self) -> Storage {
+        self.storage.unwrap_or_default()
+    }
 
-            new_ctx.accounts.iter().for_each(|new_acc| {
-                if !this_ctx.accounts.contains(new_acc) {this_ctx.accounts.push(new_acc.clone()); account_count+=1}
-            });
-    
-            new_ctx.programs.iter().for_each(|new_prog| {
-                if !this_ctx.programs.contains(new_prog) {this_ctx.programs.push(new_prog.clone()); program_count+=1}
-            });
-    
-            new_ctx.idls.iter().for_each(|new_idl| {
-                if !this_ctx.idls.contains(new_idl) {this_ctx.idls.push(new_idl.clone())}
-            });
-    
-            new_ctx.networks.iter().for_each(|new_network| {
-                this_ctx.networks.insert(new_network.clone());
-            });
-    
-            if let Some(new_overrides) = new_ctx.overrides {
-                new_overrides.iter().for_each(|new_over| {
-                    if let Some(overrides) = this_ctx.overrides.as_mut(){
-                        if !overrides.contains(new_over) {overrides.push(new_over.clone())}
-                    }
-                });
-            }
-            new_config_path = new_ctx.compose;
+    /// Rewrites all accounts and programs in a new storage format, returning the number of migrated accounts
+    pub fn migrate_storage(&mut self, storage: Storage) -> Result<usize> {
+        for account in self.programs.iter().chain(self.accounts.iter()) {
+            helpers::save_account_to_disc(&self.project_name, storage, self.discriminators.as_ref(), account)?;
         }
-        let new_context = this_ctx.to_context()?;
-        new_context.try_save_config()?;
-
-        Ok((compose_count, account_count, program_count))
+        self.storage = Some(storage);
+        self.try_save_config()?;
+        Ok(self.programs.len() + self.accounts.len())
     }
 
     pub fn has_account(&self, pubkey: &Pubkey) -> bool {
@@ -224,10 +370,118 @@ impl Valid8Context {
         self.programs.iter().any(|acc| acc.pubkey == *program_id)
     }
 
+    pub fn label(&self, pubkey: &Pubkey) -> Option<&str> {
+        self.metadata.get(pubkey).and_then(|metadata| metadata.label.as_deref())
+    }
+
+    pub fn tags(&self, pubkey: &Pubkey) -> &[String] {
+        self.metadata.get(pubkey).map_or(&[], |metadata| &metadata.tags)
+    }
+
+    /// The label of an account followed by its pubkey, or only the pubkey without a label
+    pub fn display_name(&self, pubkey: &Pubkey) -> String {
+        match self.label(pubkey) {
+            Some(label) => format!("{} ({})", label, pubkey),
+            None => pubkey.to_string(),
+        }
+    }
+
+    /// Reads a pubkey, or the label of an account or program of the config
+    pub fn resolve(&self, pubkey_or_label: &str) -> Result<Pubkey> {
+        if let Ok(pubkey) = Pubkey::from_str(pubkey_or_label) {
+            return Ok(pubkey)
+        }
+        self.metadata
+            .iter()
+            .find(|(_, metadata)| metadata.label.as_deref() == Some(pubkey_or_label))
+            .map(|(pubkey, _)| *pubkey)
+            .ok_or(anyhow!("{} is not a pubkey or a label in {}", pubkey_or_label, self.project_name.to_config()))
+    }
+
+    // Metadata is stored with the config entries, so created accounts can't have any
+    fn check_metadata_entry(&self, pubkey: &Pubkey) -> Result<()> {
+        if self.created.iter().flatten().any(|created| created.pubkey == *pubkey) {
+            return Err(anyhow!("{} is a created account, only cloned accounts and programs have labels and tags", pubkey))
+        }
+        if !self.has_account(pubkey) && !self.has_program(pubkey) {
+            return Err(anyhow!("{} is not an account or program of {}", pubkey, self.project_name.to_config()))
+        }
+        Ok(())
+    }
+
+    /// Sets or removes the label of an account or program, labels are unique and can be used instead of pubkeys
+    pub fn set_label(&mut self, pubkey: &Pubkey, label: Option<String>) -> Result<()> {
+        self.check_metadata_entry(pubkey)?;
+        if let Some(label) = label.as_deref() {
+            if label.trim().is_empty() {
+                return Err(anyhow!("Labels can't be empty"))
+            }
+            if Pubkey::from_str(label).is_ok() {
+                return Err(anyhow!("{} can't be a label, it would be read as a pubkey", label))
+            }
+            if let Some(other) = self.resolve(label).ok().filter(|other| other != pubkey) {
+                return Err(anyhow!("{} is already the label of {}", label, other))
+            }
+        }
+        self.metadata.entry(*pubkey).or_default().label = label;
+        self.try_save_config()
+    }
+
+    /// Adds tags to an account or program, tags already set are kept once
+    pub fn add_tags(&mut self, pubkey: &Pubkey, tags: &[String]) -> Result<()> {
+        self.check_metadata_entry(pubkey)?;
+        if let Some(tag) = tags.iter().find(|tag| tag.trim().is_empty() || tag.contains(',')) {
+            return Err(anyhow!("Invalid tag {:?}, tags must be non empty and without commas", tag))
+        }
+        let metadata = self.metadata.entry(*pubkey).or_default();
+        for tag in tags {
+            if !metadata.tags.contains(tag) {
+                metadata.tags.push(tag.clone());
+            }
+        }
+        self.try_save_config()
+    }
+
+    /// Removes tags from an account or program, returns the number of removed tags
+    pub fn remove_tags(&mut self, pubkey: &Pubkey, tags: &[String]) -> Result<usize> {
+        self.check_metadata_entry(pubkey)?;
+        let metadata = self.metadata.entry(*pubkey).or_default();
+        let count = metadata.tags.len();
+        metadata.tags.retain(|tag| !tags.contains(tag));
+        let removed = count - metadata.tags.len();
+        self.try_save_config()?;
+        Ok(removed)
+    }
+
+    /// Whether an account or program has any of the tags
+    pub fn has_tag(&self, pubkey: &Pubkey, tags: &[String]) -> bool {
+        self.tags(pubkey).iter().any(|tag| tags.contains(tag))
+    }
+
+    /// A copy of the context with only the accounts and programs that have any of the tags,
+    /// the program data accounts of the kept programs, and their overrides
+    pub fn tagged(&self, tags: &[String]) -> Result<Valid8Context> {
+        let mut tagged = self.clone();
+        tagged.programs.retain(|program| self.has_tag(&program.pubkey, tags));
+        let program_data = tagged.programs
+            .iter()
+            .filter(|program| program.owner == bpf_loader_upgradeable::id())
+            .map(|program| program.get_program_executable_data_address())
+            .collect::<Result<Vec<Pubkey>>>()?;
+        tagged.accounts.retain(|account| self.has_tag(&account.pubkey, tags) || program_data.contains(&account.pubkey));
+        let kept: HashSet<Pubkey> = tagged.programs.iter().chain(tagged.accounts.iter()).map(|account| account.pubkey).collect();
+        if let Some(created) = tagged.created.as_mut() {
+            created.retain(|created| kept.contains(&created.pubkey));
+        }
+        if let Some(overrides) = tagged.overrides.as_mut() {
+            overrides.retain(|over| kept.contains(&over.pubkey));
+        }
+        Ok(tagged)
+    }
+
     pub fn add_program(&mut self, network: &Network, program_id: &Pubkey) -> Result<()> {
         // Check if we have the program in our hashmap already
         if self.has_program(program_id) {
-            println!("{} already added", &program_id.to_string());
             return Ok(())
         }
         self.add_program_unchecked(network, program_id)
@@ -235,7 +489,14 @@ impl Valid8Context {
 
     pub fn add_program_unchecked(&mut self, network: &Network, program_id: &Pubkey) -> Result<()> {
         // Get program account
-        let program_account = helpers::fetch_account(network, program_id)?;
+        let (program_account, slot) = helpers::fetch_account_with_slot(network, program_id, self.offline)?;
+        self.add_fetched_program(program_account, slot)
+    }
+
+    /// Adds a program account that was already fetched at `slot`, with its program data and IDL
+    pub fn add_fetched_program(&mut self, program_account: AccountSchema, slot: u64) -> Result<()> {
+        let program_id = &program_account.pubkey;
+        self.metadata.entry(*program_id).or_default().slot = Some(slot);
 
         match program_id.to_string().as_ref() {
             "BPFLoaderUpgradeab1e11111111111111111111111" => {  },
@@ -246,10 +507,11 @@ impl Valid8Context {
 
                 // Clone program data
                 let program_data_account = helpers::clone_program_data(self, &program_account)?;
+                self.lock_fetched(&[(&program_account, slot), (&program_data_account, slot)])?;
                 self.accounts.push(program_data_account);
             
                 // Get IDL address
-                if helpers::clone_idl(&program_account).is_ok() {
+                if helpers::clone_idl(self, &program_account).is_ok() {
                     self.add_idl(program_id)?
                 }
             }
@@ -262,7 +524,6 @@ impl Valid8Context {
     pub fn add_account(&mut self, network: &Network, pubkey: &Pubkey) -> Result<()> {
         // Check if we have the account in our accounts
         if self.has_account(pubkey) {
-            println!("{} already added", &pubkey.to_string());
             return Ok(())
         }
         self.add_account_unchecked(network, pubkey)
@@ -270,24 +531,157 @@ impl Valid8Context {
 
     pub fn add_account_unchecked(&mut self, network: &Network, pubkey: &Pubkey) -> Result<()> {
         // Get account
-        let account = helpers::fetch_account(network, pubkey)?;
+        let (account, slot) = helpers::fetch_account_with_slot(network, pubkey, self.offline)?;
+        self.add_fetched_account(account, slot)
+    }
+
+    /// Adds an account that was already fetched at `slot`, and its owner program if it's missing
+    pub fn add_fetched_account(&mut self, account: AccountSchema, slot: u64) -> Result<()> {
+        let network = account.network.clone();
+        self.metadata.entry(account.pubkey).or_default().slot = Some(slot);
+        self.lock_fetched(&[(&account, slot)])?;
 
         // Save program account
-        self.accounts.push(account.clone());
+        let owner = account.owner;
+        self.accounts.push(account);
         self.networks.insert(network.clone());
 
-        match self.has_program(&account.owner) {
+        match self.has_program(&owner) {
             true => self.try_save_config(),
-            false => self.add_program_unchecked(network, &account.owner)
+            false => self.add_program_unchecked(&network, &owner)
         }
-        
+    }
+
+    pub fn import_account(&mut self, account: AccountSchema) -> Result<()> {
+        // Imported accounts replace any cloned version of the same account
+        if self.has_account(&account.pubkey) {
+            self.get_account(&account.pubkey)?;
+        }
+        helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+        self.accounts.push(account);
+        self.try_save_config()
+    }
+
+    /// Adds a program from its .so file, with the same accounts solana-test-validator creates for genesis programs
+    pub fn add_local_program(&mut self, program_id: &Pubkey, elf: &[u8], upgradeable: bool) -> Result<()> {
+        let rent = Rent::default();
+        let mut new_accounts = vec![];
+        let program = if upgradeable {
+            let (program_data_address, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+            let mut program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: None,
+            })?;
+            program_data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+            program_data.extend_from_slice(elf);
+            new_accounts.push(AccountSchema {
+                pubkey: program_data_address,
+                network: Network::Imported,
+                lamports: rent.minimum_balance(program_data.len()),
+                data: program_data,
+                owner: bpf_loader_upgradeable::id(),
+                executable: false,
+                rent_epoch: 0,
+            });
+            let program_data = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address: program_data_address })?;
+            AccountSchema {
+                pubkey: *program_id,
+                network: Network::Imported,
+                lamports: rent.minimum_balance(program_data.len()),
+                data: program_data,
+                owner: bpf_loader_upgradeable::id(),
+                executable: true,
+                rent_epoch: 0,
+            }
+        } else {
+            AccountSchema {
+                pubkey: *program_id,
+                network: Network::Imported,
+                lamports: rent.minimum_balance(elf.len()),
+                data: elf.to_vec(),
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            }
+        };
+
+        // Replace a cloned version of the program
+        self.programs.retain(|acc| acc.pubkey != *program_id);
+        self.accounts.retain(|acc| !new_accounts.iter().any(|new_acc| new_acc.pubkey == acc.pubkey));
+
+        if self.storage() != Storage::Cache {
+            helpers::save_program(&self.project_name, program_id, elf)?;
+        }
+        helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), &program)?;
+        for account in new_accounts.iter() {
+            helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), account)?;
+        }
+        self.programs.push(program);
+        self.accounts.append(&mut new_accounts);
+        self.try_save_config()
+    }
+
+    pub fn build_created_account(&self, created: &CreatedAccount) -> Result<AccountSchema> {
+        let idl = idl::open_idl(&self.project_name, &created.program_id)
+            .map_err(|e| anyhow!("No IDL for {} found, add it with valid8 idl add: {}", created.program_id, e))?;
+        let raw_idl = idl::open_idl_json(&self.project_name, &created.program_id)?;
+        let strategy = self.discriminator_strategy(&created.program_id, &idl);
+        let idl_type_def = idl.accounts
+            .iter()
+            .find(|a| a.name == created.account_type)
+            .ok_or(anyhow!("Account type {} not found in IDL {}", created.account_type, idl.name))?;
+
+        let mut data = idl::pack_idl_account(&idl, &raw_idl, idl_type_def, strategy, &created.values)?;
+        if let Some(space) = created.space {
+            if space < data.len() {
+                return Err(anyhow!("Account space {} is smaller than the {} serialized bytes", space, data.len()));
+            }
+            data.resize(space, 0);
+        }
+
+        Ok(AccountSchema {
+            pubkey: created.pubkey,
+            network: Network::Imported,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: created.program_id,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    pub fn create_account(&mut self, created: CreatedAccount) -> Result<()> {
+        if self.has_account(&created.pubkey) {
+            return Err(anyhow!("{} already added", created.pubkey));
+        }
+        let account = self.build_created_account(&created)?;
+
+        helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+        self.accounts.push(account);
+        // keep the definition so the account is rebuilt on install
+        self.created.get_or_insert_with(Vec::new).push(created);
+        self.try_save_config()
     }
 
     pub fn add_idl(&mut self, program_id: &Pubkey) -> Result<()> {
-        self.idls.push(program_id.to_string());
+        if !self.idls.contains(&program_id.to_string()) {
+            self.idls.push(program_id.to_string());
+        }
         Ok(())
     }
 
+    pub fn add_local_idl(&mut self, program_id: &Pubkey, path: &str) -> Result<()> {
+        // Make sure the file is a valid IDL before registering it
+        let idl = helpers::read_local_idl(path)?;
+        helpers::save_idl(&self.project_name, program_id, &idl)?;
+
+        self.local_idls
+            .get_or_insert_with(BTreeMap::new)
+            .insert(program_id.to_string(), path.to_string());
+        self.add_idl(program_id)?;
+        self.try_save_config()
+    }
+
     pub fn get_account(&mut self, pubkey: &Pubkey,) -> Result<AccountSchema> {
         let position = self.accounts
             .iter()
@@ -297,202 +691,632 @@ impl Valid8Context {
         Ok(self.accounts.remove(position))
     }
 
+    /// Records an edit, replacing an earlier edit of the same value of the account
     pub fn add_override(&mut self, over: Override) {
-        if let Some(override_list) = self.overrides.as_mut() {
-            if !override_list.contains(&over) {
-                override_list.push(over)
+        let override_list = self.overrides.get_or_insert_with(Vec::new);
+        for edit_field in over.edit_fields {
+            // interactive edits of older versions aren't replayed, so they aren't recorded anymore
+            if matches!(edit_field, EditField::UnpackTokenAccount | EditField::UnpackPDA) {
+                continue
+            }
+            override_list
+                .iter_mut()
+                .filter(|existing| existing.pubkey == over.pubkey)
+                .for_each(|existing| existing.edit_fields.retain(|existing_field| !existing_field.same_target(&edit_field)));
+            match override_list.iter_mut().find(|existing| existing.pubkey == over.pubkey) {
+                Some(existing) => existing.edit_fields.push(edit_field),
+                None => override_list.push(Override::new(over.pubkey, edit_field)),
             }
-        } else {
-            self.overrides = Some(vec![over])
         }
+        override_list.retain(|existing| !existing.edit_fields.is_empty());
     }
 
+    /// Replays all overrides of the config on the accounts in the context and saves them to disc.
+    /// Overrides are applied in the order of the config, without prompts or config changes, and replaying them again changes nothing
     pub fn apply_overrides(&mut self) -> Result<()> {
-        // iterate through all overrides from the config, and apply them on all accounts and programs
-        if let Some (override_list) = self.overrides.clone() {
-            override_list.iter().map(|over| {
-                if self.accounts.iter().any(|acc| acc.pubkey == over.pubkey) {
-                    over.edit_fields
-                        .iter()
-                        .map(|edit_field| self.edit_account(&over.pubkey, edit_field.clone()))
-                        .collect::<Result<Vec<()>>>()
-                        
-                } else if self.programs.iter().any(|acc| acc.pubkey == over.pubkey) { 
-                    over.edit_fields
-                        .iter()
-                        .map(|edit_field| self.edit_program(None, &over.pubkey, None, edit_field.clone()))
-                        .collect::<Result<Vec<()>>>()
-                } else {
-                    Err(anyhow!("Account not found in context!: {}", over.pubkey))
-                }
-            }).collect::<Result<Vec<_>>>()?;
+        for over in self.overrides.clone().iter().flatten() {
+            self.apply_override(over)?;
         }
         Ok(())
     }
 
-    pub fn edit_account(&mut self, pubkey: &Pubkey, edit_field: EditField) -> Result<()> {
-       
-       // get the account from the context
-        let mut account = self.get_account(pubkey)?;
+    fn apply_override(&mut self, over: &Override) -> Result<()> {
+        let mut account = self.accounts
+            .iter()
+            .chain(self.programs.iter())
+            .find(|acc| acc.pubkey == over.pubkey)
+            .cloned()
+            .ok_or(anyhow!("Account not found in context!: {}", over.pubkey))?;
+        over.edit_fields
+            .iter()
+            .try_for_each(|edit_field| self.apply_edit(&mut account, edit_field))
+            .map_err(|e| anyhow!("Override of {} failed: {}", over.pubkey, e))?;
 
-        match edit_field {
-            EditField::Lamports(new_lamports) => {
-                account.lamports = new_lamports
+        helpers::save_account_to_disc(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+        for existing in self.accounts.iter_mut().chain(self.programs.iter_mut()).filter(|acc| acc.pubkey == over.pubkey) {
+            *existing = account.clone();
+        }
+        Ok(())
+    }
+
+    // Locks the fetched state of accounts that were added or updated on purpose, and keeps their pristine copies
+    fn lock_fetched(&self, fetched: &[(&AccountSchema, u64)]) -> Result<()> {
+        let mut lock = Lockfile::read(&self.project_name)?;
+        for (account, slot) in fetched {
+            storage::save_pristine(&self.project_name, account)?;
+            lock.lock(account, *slot);
+        }
+        lock.save(&self.project_name)
+    }
+
+    /// Fetches the current state of cloned accounts and programs, or only of `pubkeys`, and locks it.
+    /// Overrides are applied again, returns each updated account with its changes since it was locked
+    pub fn update(&mut self, pubkeys: &[Pubkey]) -> Result<Vec<(Pubkey, Vec<String>)>> {
+        let cloned: Vec<(Pubkey, Network)> = self.programs
+            .iter()
+            .chain(self.accounts.iter())
+            .filter(|account| account.network != Network::Imported)
+            .map(|account| (account.pubkey, account.network.clone()))
+            .collect();
+        if let Some(pubkey) = pubkeys.iter().find(|pubkey| !cloned.iter().any(|(cloned, _)| cloned == *pubkey)) {
+            return Err(anyhow!("{} is not a cloned account or program of {}", pubkey, self.project_name.to_config()))
+        }
+
+        let mut lock = Lockfile::read(&self.project_name)?;
+        let mut updated = vec![];
+        for (pubkey, network) in cloned.into_iter().filter(|(pubkey, _)| pubkeys.is_empty() || pubkeys.contains(pubkey)) {
+            let (account, slot) = helpers::fetch_account_with_slot(&network, &pubkey, self.offline)?;
+            let mut fetched = vec![account];
+            if fetched[0].executable && fetched[0].owner == bpf_loader_upgradeable::id() {
+                fetched.push(helpers::clone_program_data(self, &fetched[0])?);
             }
-            EditField::Owner(new_owner) => {
-                account.owner = new_owner
-            },
-            EditField::UnpackTokenAccount => {
-                // deserialize token account data to Account struct for editing
-                let mut token_account = TokenAccount::unpack(&account.data)?;
-
-                let fields: Vec<String> = vec![
-                    format!("Owner: {}", token_account.owner.to_string()),
-                    format!("Token amount: {}", token_account.amount.to_string()),
-                    format!("Token delegate: {:?}", token_account.delegate),
-                    format!("Token delegate amount: {}", token_account.delegated_amount),
-                ];
-
-                let selection = Select::new().with_prompt("Select a field to edit").items(&fields).interact()?;
-
-                match selection {
-                    0 => {
-                        let new_owner: Pubkey = Input::new().with_prompt("New owner pubkey:").interact_text()?;
-                        token_account.owner = new_owner;
-                    }
-                    1 => {
-                        let new_amount: u64 = Input::new().with_prompt("New amount:").interact_text()?;
-                        token_account.amount = new_amount;
-                    }
-                    2 => {
-                        let new_delegate: Pubkey = Input::new().with_prompt("New delegate pubkey:").interact_text()?;
-                        token_account.delegate = Some(new_delegate).into();
-                    }
-                    3 => {
-                        let new_delegate_amount: u64 = Input::new().with_prompt("New delegate amount:").interact_text()?;
-                        token_account.delegated_amount = new_delegate_amount;
-                    }
-                    4 => {}
-                    _ => return Err(anyhow!("Invalid token account edit option"))
+            for account in fetched {
+                let changes = match lock.accounts.get(&account.pubkey.to_string()) {
+                    Some(locked) => locked.changes(&LockedAccount::new(&account, slot)),
+                    None => vec!["not locked before".to_string()],
+                };
+                lock.lock(&account, slot);
+                self.metadata.entry(account.pubkey).or_default().slot = Some(slot);
+                helpers::save_cloned_account(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+                match self.programs.iter().position(|program| program.pubkey == account.pubkey) {
+                    Some(index) => self.programs[index] = account.clone(),
+                    None => {
+                        self.accounts.retain(|existing| existing.pubkey != account.pubkey);
+                        self.accounts.push(account.clone());
+                    },
                 }
-
-                let mut new_data = [0u8;TokenAccount::LEN];
-                token_account.pack_into_slice(&mut new_data);
-                account.data = new_data.to_vec();
-            },
-            _ => return Err(anyhow!("Invalid option")),
+                updated.push((account.pubkey, changes));
+            }
         }
 
-        helpers::save_account_to_disc(&self.project_name, &account)?;
-        self.accounts.push(account);
-        self.add_override(Override::new(*pubkey, edit_field));
+        // Fetched accounts replace the overridden ones, so their overrides are applied again
+        for over in self.overrides.clone().iter().flatten() {
+            if updated.iter().any(|(pubkey, _)| *pubkey == over.pubkey) {
+                self.apply_override(over)?;
+            }
+        }
+        lock.save(&self.project_name)?;
         self.try_save_config()?;
-        
-        Ok(())
+        Ok(updated)
     }
 
-    pub fn edit_program(&mut self, program_account: Option<&Pubkey>, program_data_account: &Pubkey, program_pda: Option<&Pubkey>, edit_field: EditField) -> Result<()> {
+    /// Compares accounts and programs, or only `pubkeys`, with their live state on their own network or on `network`.
+    /// Local accounts are only compared on a given network
+    pub fn diff(&self, pubkeys: &[Pubkey], network: Option<&Network>) -> Result<Vec<AccountDiff>> {
+        if let Some(pubkey) = pubkeys.iter().find(|pubkey| !self.has_account(pubkey) && !self.has_program(pubkey)) {
+            return Err(anyhow!("{} is not an account or program of {}", pubkey, self.project_name.to_config()))
+        }
 
-        let mut program_data = self.get_account(program_data_account)?;
+        let mut diffs = vec![];
+        for local in self.programs.iter().chain(self.accounts.iter()) {
+            if !pubkeys.is_empty() && !pubkeys.contains(&local.pubkey) {
+                continue
+            }
+            let network = match network.or(Some(&local.network).filter(|network| **network != Network::Imported)) {
+                Some(network) => network,
+                None if pubkeys.is_empty() => continue,
+                None => return Err(anyhow!("{} is an imported account, use --network to compare it with a network", local.pubkey)),
+            };
+            let (live, slot) = helpers::fetch_live_account(network, &local.pubkey, self.offline)?;
+
+            let idl = idl::open_idl(&self.project_name, &local.owner).ok();
+            let raw_idl = idl::open_idl_json(&self.project_name, &local.owner).ok();
+            let owner_idl = idl.as_ref().zip(raw_idl.as_ref()).map(|(idl, raw_idl)| {
+                OwnerIdl { idl, raw_idl, strategy: self.discriminator_strategy(&local.owner, idl) }
+            });
 
-        let mut changed = true;
+            // Program overrides edit the program data account
+            let program_id = self.programs
+                .iter()
+                .find(|program| program.owner == bpf_loader_upgradeable::id() && program.executable && program.get_program_executable_data_address().ok() == Some(local.pubkey))
+                .map_or(local.pubkey, |program| program.pubkey);
+            let overrides: Vec<&EditField> = self.overrides
+                .iter()
+                .flatten()
+                .filter(|over| over.pubkey == local.pubkey || over.pubkey == program_id)
+                .flat_map(|over| over.edit_fields.iter())
+                .collect();
+
+            diffs.push(diff::diff_account(local, live.as_ref(), network, slot, owner_idl, &overrides));
+        }
+        Ok(diffs)
+    }
 
-        match &edit_field {
-            EditField::Lamports(new_lamports) => {
-                // change the amount of lamports the account holds
-                program_data.lamports = *new_lamports;
-            }
-            EditField::Owner(new_owner) => {
-                // change the owner of the account
-                program_data.owner = *new_owner
-            },
+    /// Edits an account and records the edit as an override of the config
+    pub fn edit_account(&mut self, pubkey: &Pubkey, edit_field: EditField) -> Result<()> {
+        let account = self.edit_account_unsaved(pubkey, edit_field)?;
+        helpers::save_account_to_disc(&self.project_name, self.storage(), self.discriminators.as_ref(), &account)?;
+        self.try_save_config()
+    }
+
+    // Edits an account and records the override in the context only, the account is unchanged if the edit fails
+    fn edit_account_unsaved(&mut self, pubkey: &Pubkey, edit_field: EditField) -> Result<AccountSchema> {
+        let position = self.accounts
+            .iter()
+            .position(|acc| acc.pubkey == *pubkey)
+            .ok_or(anyhow!("No account found in context; Edit"))?;
+        let mut account = self.accounts[position].clone();
+        self.apply_edit(&mut account, &edit_field)?;
+        self.accounts[position] = account.clone();
+        self.add_override(Override::new(*pubkey, edit_field));
+        Ok(account)
+    }
+
+    /// Edits the program data account of a program, like `edit_account`
+    pub fn edit_program(&mut self, program_data_account: &Pubkey, edit_field: EditField) -> Result<()> {
+        self.edit_account(program_data_account, edit_field)
+    }
+
+    /// Sets the edited value on an account, without saving anything.
+    /// Every edit sets an explicit value, so applying it again doesn't change the account
+    pub fn apply_edit(&self, account: &mut AccountSchema, edit_field: &EditField) -> Result<()> {
+        match edit_field {
+            EditField::Lamports(new_lamports) => account.lamports = *new_lamports,
+            EditField::Owner(new_owner) => account.owner = *new_owner,
             EditField::UpgradeAuthority(new_upgrade_auth) => {
-                // serialize new accont data state and add it to the account data field, with new upgrade authority and slot 0
-                let new_statue = UpgradeableLoaderState::ProgramData {
+                // only program data accounts have an upgrade authority, it's set with slot 0
+                let is_program_data = account.owner == bpf_loader_upgradeable::id()
+                    && matches!(bincode::deserialize(&account.data), Ok(UpgradeableLoaderState::ProgramData { .. }));
+                if !is_program_data {
+                    return Err(anyhow!("{} is not a program data account, can't set its upgrade authority", account.pubkey))
+                }
+                let new_state = UpgradeableLoaderState::ProgramData {
                     slot: 0,
                     upgrade_authority_address: Some(*new_upgrade_auth),
                 };
-                let network = &program_data.network.clone();
-                let mut acc = program_data.to_account()?;
-                acc.set_state(&new_statue)?;
-                program_data = AccountSchema::from_account(&acc, program_data_account, network)?;
+                let mut acc = account.to_account()?;
+                acc.set_state(&new_state)?;
+                *account = AccountSchema::from_account(&acc, &account.pubkey, &account.network)?;
             },
-            EditField::UnpackTokenAccount => { },
-            EditField::UnpackPDA => {
-                let idl = idl::open_idl(program_account.ok_or(anyhow!("No program key to edit pda"))?)?;
-                let map = idl::generate_discriminator_map(&idl)?;
-
-                // Get the account from context to edit
-                let mut pda = self.get_account(program_pda.unwrap())?;
-                println!("pda found {:?}", pda);
-                
-                // iterate on the idl map to find the right account discriminator
-                let _ = map.iter().map(|(key,idl_type_def)| {
-                    if key == &pda.data[..8] {
-                        // create a mutable buffer for new account data, adding the discriminator as first part of the buffer
-                        let mut new_acc_data = key.to_vec();
-
-                        // unpack the pda account data to a vector of idl account fields
-                        if let Ok(mut return_val) = unpack_idl_account(idl_type_def, pda.data[8..].to_vec()).map_err(|e|anyhow!(e))  {
-                            
-                            // create a vector from the deserialized values for the user to select from
-                            let account_fields = return_val.iter().map(|field| {
-                                format!("{}: {:?}",field.name, field.value)
-                            }).collect::<Vec<String>>();
-
-                            let selection = Select::new()
-                                .with_prompt("Select PDA field to edit.")
-                                .items(&account_fields)
-                                .interact_opt()?;
-
-                            let new_value: String = Input::new().with_prompt("New value").interact_text()?;
-                
-                            
-                            if let Some(index_select) = selection {
-                                let _ = return_val.iter_mut().enumerate().map(|(index,field)| {
-
-                                    // find the user selected field in the vector, and edit it
-                                    if index == index_select {
-                                        field.edit(new_value.clone())?;
-                                    }
-                                    // serialize all idl account fields with borsh and add them to the new account data vector
-                                    new_acc_data.extend_from_slice(&field.to_bytes()?);
-                                    Ok(())
-
-                                }).collect::<Result<Vec<()>>>()?;
-                            }
-                        }
-
-                        // copy the newly created account data vector to the pda data field
-                        pda.data[..new_acc_data.len()].copy_from_slice(&new_acc_data);
-                        println!("changed data: {:?}", &pda);
-                    }
-                    Ok(())
-                     
-                }).collect::<Result<Vec<()>>>()?;
-                helpers::save_account_to_disc(&self.project_name, &pda)?;
-                self.accounts.push(pda);
-                changed = false;
+            EditField::TokenOwner(new_owner) => {
+                Self::edit_token_account(account, |token_account| token_account.owner = *new_owner)?
+            },
+            EditField::TokenAmount(new_amount) => {
+                Self::edit_token_account(account, |token_account| token_account.amount = *new_amount)?
+            },
+            EditField::TokenDelegate(new_delegate) => {
+                Self::edit_token_account(account, |token_account| token_account.delegate = Some(*new_delegate).into())?
             },
-            _ => return Err(anyhow!("Invalid option")),
+            EditField::TokenDelegatedAmount(new_delegated_amount) => {
+                Self::edit_token_account(account, |token_account| token_account.delegated_amount = *new_delegated_amount)?
+            },
+            EditField::IdlField { program_id, account_type, field, value } => {
+                self.set_idl_field(program_id, account, account_type, field, value.clone())?
+            },
+            // relocations are applied to a copy of the context, when building ledgers and exports
+            EditField::Relocate { .. } | EditField::UnpackTokenAccount | EditField::UnpackPDA => { },
+            EditField::Data(_) => return Err(anyhow!("Raw data edits aren't supported, edit the IDL fields of {} instead", account.pubkey)),
+        }
+        Ok(())
+    }
 
+    /// Reads the value an edit sets from an account, None if the account doesn't have it
+    pub fn edit_value(&self, account: &AccountSchema, edit_field: &EditField) -> Option<String> {
+        match edit_field {
+            EditField::Lamports(_) => Some(account.lamports.to_string()),
+            EditField::Owner(_) => Some(account.owner.to_string()),
+            EditField::UpgradeAuthority(_) => match bincode::deserialize(&account.data).ok()? {
+                UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. } => {
+                    Some(upgrade_authority_address.map_or("none".to_string(), |authority| authority.to_string()))
+                },
+                _ => None,
+            },
+            EditField::TokenOwner(_) => Some(TokenAccount::unpack(&account.data).ok()?.owner.to_string()),
+            EditField::TokenAmount(_) => Some(TokenAccount::unpack(&account.data).ok()?.amount.to_string()),
+            EditField::TokenDelegate(_) => {
+                Some(Option::<Pubkey>::from(TokenAccount::unpack(&account.data).ok()?.delegate).map_or("none".to_string(), |delegate| delegate.to_string()))
+            },
+            EditField::TokenDelegatedAmount(_) => Some(TokenAccount::unpack(&account.data).ok()?.delegated_amount.to_string()),
+            EditField::IdlField { program_id, account_type, field, .. } => {
+                let unpacked = self.unpack_idl_data(program_id, &account.data, account_type).ok()?;
+                unpacked.fields.into_iter().find(|unpacked_field| unpacked_field.name == *field)?.value.map(|value| value.to_string())
+            },
+            EditField::Data(_) | EditField::Relocate { .. } | EditField::UnpackTokenAccount | EditField::UnpackPDA => None,
         }
-        // save edited account to disc for persistence
-        helpers::save_account_to_disc(&self.project_name, &program_data)?;
-        // add edited account back to context 
-        self.accounts.push(program_data);
-        if changed {
-            // if an override happened, add it to the overrides list
-            self.add_override(Override::new(*program_data_account, edit_field));
+    }
+
+    /// Lists every override with the value it replaced in the pristine account and the current value
+    pub fn override_states(&self) -> Result<Vec<OverrideState>> {
+        let mut states = vec![];
+        for over in self.overrides.iter().flatten() {
+            let current = self.accounts.iter().chain(self.programs.iter()).find(|acc| acc.pubkey == over.pubkey);
+            let pristine = storage::read_pristine(&self.project_name, &over.pubkey)?;
+            for edit_field in over.edit_fields.iter() {
+                states.push(OverrideState {
+                    pubkey: over.pubkey,
+                    edit_field: edit_field.clone(),
+                    original: match edit_field {
+                        EditField::Relocate { .. } => Some(over.pubkey.to_string()),
+                        _ => pristine.as_ref().and_then(|pristine| self.edit_value(pristine, edit_field)),
+                    },
+                    current: match edit_field {
+                        EditField::Relocate { to, .. } => Some(to.to_string()),
+                        _ => current.and_then(|current| self.edit_value(current, edit_field)),
+                    },
+                });
+            }
+        }
+        Ok(states)
+    }
+
+    /// Removes the overrides of an account, or only the one of `field`, and restores the pristine account with the remaining overrides.
+    /// Accounts without a pristine copy are cloned again from their network. Returns the number of removed overrides
+    pub fn revert_override(&mut self, pubkey: &Pubkey, field: Option<&str>) -> Result<usize> {
+        let override_list = self.overrides.get_or_insert_with(Vec::new);
+        let overridden: Vec<String> = override_list
+            .iter()
+            .filter(|over| over.pubkey == *pubkey)
+            .flat_map(|over| over.edit_fields.iter().map(|edit_field| edit_field.field_name()))
+            .collect();
+        if overridden.is_empty() {
+            return Err(anyhow!("{} has no overrides", pubkey))
+        }
+        if let Some(field) = field.filter(|field| !overridden.iter().any(|name| name == field)) {
+            return Err(anyhow!("{} has no override of {}, overridden: {}", pubkey, field, overridden.join(", ")))
+        }
+        // Relocations don't change the saved account, so they're removed without restoring it
+        let reverted = |edit_field: &EditField| field.is_none_or(|field| edit_field.field_name() == field);
+        let reverted_fields: Vec<&EditField> = override_list
+            .iter()
+            .filter(|over| over.pubkey == *pubkey)
+            .flat_map(|over| over.edit_fields.iter())
+            .filter(|edit_field| reverted(edit_field))
+            .collect();
+        if reverted_fields.iter().all(|edit_field| matches!(edit_field, EditField::Relocate { .. })) {
+            let removed = reverted_fields.len();
+            override_list
+                .iter_mut()
+                .filter(|over| over.pubkey == *pubkey)
+                .for_each(|over| over.edit_fields.retain(|edit_field| !reverted(edit_field)));
+            override_list.retain(|over| !over.edit_fields.is_empty());
+            self.try_save_config()?;
+            return Ok(removed)
+        }
+
+        let current = self.accounts
+            .iter()
+            .chain(self.programs.iter())
+            .find(|acc| acc.pubkey == *pubkey)
+            .ok_or(anyhow!("Account not found in context!: {}", pubkey))?;
+        let pristine = match storage::read_pristine(&self.project_name, pubkey)? {
+            Some(pristine) => pristine,
+            None if current.network != Network::Imported => {
+                let account = helpers::fetch_account(&current.network, pubkey, self.offline)?;
+                storage::save_pristine(&self.project_name, &account)?;
+                account
+            },
+            None => return Err(anyhow!("No original of {} saved, it can't be reverted", pubkey)),
+        };
+
+        let override_list = self.overrides.get_or_insert_with(Vec::new);
+        let mut removed = 0;
+        for over in override_list.iter_mut().filter(|over| over.pubkey == *pubkey) {
+            let before = over.edit_fields.len();
+            over.edit_fields.retain(|edit_field| field.is_some_and(|field| edit_field.field_name() != field));
+            removed += before - over.edit_fields.len();
+        }
+        override_list.retain(|over| !over.edit_fields.is_empty());
+
+        // The remaining overrides are replayed on the pristine account
+        helpers::save_account_to_disc(&self.project_name, self.storage(), self.discriminators.as_ref(), &pristine)?;
+        for existing in self.accounts.iter_mut().chain(self.programs.iter_mut()).filter(|acc| acc.pubkey == *pubkey) {
+            *existing = pristine.clone();
+        }
+        for over in self.overrides.clone().iter().flatten().filter(|over| over.pubkey == *pubkey) {
+            self.apply_override(over)?;
         }
         self.try_save_config()?;
+        Ok(removed)
+    }
+
+    fn edit_token_account(account: &mut AccountSchema, edit: impl FnOnce(&mut TokenAccount)) -> Result<()> {
+        // deserialize token account data to Account struct for editing
+        let mut token_account = TokenAccount::unpack(&account.data)?;
+        edit(&mut token_account);
+
+        let mut new_data = [0u8;TokenAccount::LEN];
+        token_account.pack_into_slice(&mut new_data);
+        account.data = new_data.to_vec();
+        Ok(())
+    }
+
+    pub fn discriminator_strategy(&self, program_id: &Pubkey, idl: &Idl) -> DiscriminatorStrategy {
+        // An explicitly configured strategy wins over the one detected from the IDL
+        DiscriminatorStrategy::configured(self.discriminators.as_ref(), program_id, idl)
+    }
+
+    pub fn set_discriminator_strategy(&mut self, program_id: &Pubkey, strategy: DiscriminatorStrategy) -> Result<()> {
+        self.discriminators
+            .get_or_insert_with(BTreeMap::new)
+            .insert(program_id.to_string(), strategy);
+        self.try_save_config()
+    }
+
+    /// Finds the IDL account type of an account from its discriminator
+    pub fn find_idl_account_type(&self, program_id: &Pubkey, pubkey: &Pubkey) -> Result<Option<String>> {
+        let idl = idl::open_idl(&self.project_name, program_id)?;
+        let strategy = self.discriminator_strategy(program_id, &idl);
+        let account = self.accounts
+            .iter()
+            .find(|acc| acc.pubkey == *pubkey)
+            .ok_or(anyhow!("No account found in context"))?;
+        Ok(idl::find_account_type(&idl, strategy, &account.data)?.map(|(_, idl_type_def)| idl_type_def.name))
+    }
 
+    /// Unpacks an account to its IDL fields, as the given account type
+    pub fn unpack_idl_account(&self, program_id: &Pubkey, pubkey: &Pubkey, account_type: &str) -> Result<UnpackedIdlAccount> {
+        let account = self.accounts
+            .iter()
+            .find(|acc| acc.pubkey == *pubkey)
+            .ok_or(anyhow!("No account found in context"))?;
+        self.unpack_idl_data(program_id, &account.data, account_type)
+    }
+
+    fn unpack_idl_data(&self, program_id: &Pubkey, data: &[u8], account_type: &str) -> Result<UnpackedIdlAccount> {
+        let idl = idl::open_idl(&self.project_name, program_id)?;
+        let raw_idl = idl::open_idl_json(&self.project_name, program_id)?;
+        let strategy = self.discriminator_strategy(program_id, &idl);
+
+        // the discriminator is only skipped if it matches the account type
+        let discriminator_len = idl::find_account_type(&idl, strategy, data)?
+            .filter(|(_, idl_type_def)| idl_type_def.name == account_type)
+            .map_or(0, |(discriminator_len, _)| discriminator_len);
+        let idl_type_def = idl.accounts
+            .iter()
+            .find(|idl_type_def| idl_type_def.name == account_type)
+            .cloned()
+            .ok_or(anyhow!("Account type {} not found in IDL of {}", account_type, program_id))?;
+
+        // unpack the account data to a vector of idl account fields
+        let fields = idl::unpack_account(&idl, &raw_idl, &idl_type_def, &data[discriminator_len..])?;
+        Ok(UnpackedIdlAccount { discriminator_len, idl_type_def, fields })
+    }
+
+    /// Sets a single IDL field of an account and records it as an override, nested fields are named like `pool.mint` or `orders[3].price`
+    pub fn edit_idl_account(&mut self, program_id: &Pubkey, pubkey: &Pubkey, account_type: &str, field_name: &str, new_value: String) -> Result<()> {
+        let edit_field = EditField::IdlField {
+            program_id: *program_id,
+            account_type: account_type.to_string(),
+            field: field_name.to_string(),
+            value: new_value,
+        };
+        self.edit_account(pubkey, edit_field)
+    }
+
+    fn set_idl_field(&self, program_id: &Pubkey, account: &mut AccountSchema, account_type: &str, field_name: &str, new_value: String) -> Result<()> {
+        let raw_idl = idl::open_idl_json(&self.project_name, program_id)?;
+        let UnpackedIdlAccount { discriminator_len, idl_type_def, mut fields } = self.unpack_idl_data(program_id, &account.data, account_type)?;
+
+        let index = fields
+            .iter()
+            .position(|field| field.name == field_name)
+            .ok_or(anyhow!("Field {} not found in {}", field_name, account_type))?;
+        fields[index].edit(new_value)?;
+
+        match idl::type_serialization(&raw_idl, &idl_type_def.name) {
+            Serialization::ZeroCopy { .. } => {
+                // zero copy fields have a fixed offset, so the edited field is written in place
+                let field = &fields[index];
+                let start = discriminator_len + field.offset;
+                let bytes = field.to_bytes()?;
+                if bytes.len() != field.field_len {
+                    return Err(anyhow!("{} is {} bytes, the new value is {} bytes", field.name, field.field_len, bytes.len()))
+                }
+                account.data[start..start + field.field_len].copy_from_slice(&bytes);
+            },
+            Serialization::Borsh => {
+                // only the edited field is serialized again, the others keep their bytes,
+                // as well as the discriminator and any data after the decoded fields
+                let decoded_len: usize = fields.iter().map(|field| field.field_len).sum();
+                let mut new_data = account.data[..discriminator_len].to_vec();
+                for (i, field) in fields.iter().enumerate() {
+                    match i == index {
+                        true => new_data.extend_from_slice(&field.to_bytes()?),
+                        false => new_data.extend_from_slice(&account.data[discriminator_len + field.offset..discriminator_len + field.offset + field.field_len]),
+                    }
+                }
+                new_data.extend_from_slice(&account.data[discriminator_len + decoded_len..]);
+                if new_data.len() < account.data.len() {
+                    new_data.resize(account.data.len(), 0);
+                }
+                account.data = new_data;
+            },
+        }
         Ok(())
     }
 
-    pub fn create_ledger(self) -> Result<()> {
+    /// Moves an account or program to a new address in ledgers and exports, recorded as an override.
+    /// With `rewrite_references` the old address is replaced in the data and owner of all accounts, eg. for a fork of a program,
+    /// programs and program data are only rewritten with `rewrite_programs`.
+    /// Returns the number of references replaced in each account by all relocations, by the address before relocation
+    pub fn relocate_account(&mut self, pubkey: &Pubkey, to: &Pubkey, rewrite_references: bool, rewrite_programs: bool) -> Result<BTreeMap<Pubkey, usize>> {
+        if !self.has_account(pubkey) && !self.has_program(pubkey) {
+            return Err(anyhow!("{} is not an account or program of {}", pubkey, self.project_name.to_config()))
+        }
+        if self.has_account(to) || self.has_program(to) {
+            return Err(anyhow!("Can't relocate {} to {}, it's already an account or program", pubkey, to))
+        }
+        self.add_override(Override::new(*pubkey, EditField::Relocate { to: *to, rewrite_references, rewrite_programs }));
+        let (_, rewrites) = self.relocated_with_rewrites()?;
+        self.try_save_config()?;
+        Ok(rewrites)
+    }
+
+    /// Moves a program and its accounts to a new program id with overrides, eg. for the fixtures of a fork deployed under another id.
+    /// Accounts owned by the program get the new owner, PDAs are derived again where their seeds are found with the IDL,
+    /// and the program is relocated with its program data, rewriting references to all old addresses.
+    /// Nothing is saved unless all overrides can be applied, the config is saved once
+    pub fn rebase_program(&mut self, from: &Pubkey, to: &Pubkey) -> Result<RebaseReport> {
+        let owned: Vec<AccountSchema> = self.accounts.iter().filter(|acc| acc.owner == *from).cloned().collect();
+        if owned.is_empty() && !self.has_program(from) {
+            return Err(anyhow!("{} is not a program of {} and owns no accounts", from, self.project_name.to_config()))
+        }
+        let idl = idl::open_idl(&self.project_name, from).ok();
+        let raw_idl = idl::open_idl_json(&self.project_name, from).ok();
+
+        let mut report = RebaseReport::default();
+        let mut pdas = vec![];
+        for account in owned.iter() {
+            match idl.as_ref().zip(raw_idl.as_ref()).and_then(|(idl, raw_idl)| pda::find_pda_seeds(self, idl, raw_idl, from, account)) {
+                Some((seeds, bump)) => pdas.push((account.pubkey, seeds, bump)),
+                None => report.not_derived.push(account.pubkey),
+            }
+        }
+
+        // Seeds can contain the program id or other PDAs, so they're derived again until no address changes
+        let mut addresses: HashMap<Pubkey, (Pubkey, u8)> = HashMap::new();
+        for _ in 0..=pdas.len() {
+            let mut changed = false;
+            for (pubkey, seeds, _) in pdas.iter() {
+                let new_seeds: Vec<Vec<u8>> = seeds
+                    .iter()
+                    .map(|seed| match Pubkey::try_from(seed.as_slice()) {
+                        Ok(seed_pubkey) if seed_pubkey == *from => to.to_bytes().to_vec(),
+                        Ok(seed_pubkey) => addresses.get(&seed_pubkey).map_or(seed.clone(), |(address, _)| address.to_bytes().to_vec()),
+                        Err(_) => seed.clone(),
+                    })
+                    .collect();
+                let seed_slices = new_seeds.iter().map(|s| s.as_slice()).collect::<Vec<&[u8]>>();
+                let derived = Pubkey::try_find_program_address(&seed_slices, to).ok_or(anyhow!("No PDA for the seeds of {} with {}", pubkey, to))?;
+                changed |= addresses.insert(*pubkey, derived) != Some(derived);
+            }
+            if !changed {
+                break
+            }
+        }
+
+        // All changes are made on a copy, which only replaces this context when every override applies
+        let mut rebased = self.clone();
+        let mut edited = HashSet::new();
+        let relocate = |to: Pubkey| EditField::Relocate { to, rewrite_references: true, rewrite_programs: false };
+        for (pubkey, _, bump) in pdas.iter() {
+            let (address, new_bump) = *addresses.get(pubkey).ok_or(anyhow!("No PDA derived again for {}", pubkey))?;
+            // Anchor accounts usually store their bump, it's replaced with the bump of the new address
+            if let Some(account_type) = rebased.find_idl_account_type(from, pubkey)? {
+                let stores_bump = rebased.unpack_idl_account(from, pubkey, &account_type)?
+                    .fields
+                    .iter()
+                    .any(|field| field.name == "bump" && matches!(field.value, Some(FieldValue::Number(value)) if value == u128::from(*bump)));
+                if stores_bump && new_bump != *bump {
+                    let edit_field = EditField::IdlField { program_id: *from, account_type, field: "bump".to_string(), value: new_bump.to_string() };
+                    rebased.edit_account_unsaved(pubkey, edit_field)?;
+                    edited.insert(*pubkey);
+                }
+            }
+            rebased.add_override(Override::new(*pubkey, relocate(address)));
+            report.relocated.push((*pubkey, address));
+        }
 
-        // create a solana-test-validator compatible ledger directory with account and programs added
+        // Relocating the program rewrites the owner of its accounts, they only need an owner override when it stays
+        report.program_relocated = self.has_program(from) && !self.has_program(to) && !self.has_account(to);
+        for account in owned.iter() {
+            if !report.program_relocated {
+                rebased.edit_account_unsaved(&account.pubkey, EditField::Owner(*to))?;
+                edited.insert(account.pubkey);
+            }
+            report.owners.push(account.pubkey);
+        }
+        if report.program_relocated {
+            rebased.add_override(Override::new(*from, relocate(*to)));
+        }
+
+        rebased.relocated()?;
+        for account in rebased.accounts.iter().filter(|account| edited.contains(&account.pubkey)) {
+            helpers::save_account_to_disc(&self.project_name, self.storage(), self.discriminators.as_ref(), account)?;
+        }
+        *self = rebased;
+        self.try_save_config()?;
+        Ok(report)
+    }
+
+    /// A copy of the context with all relocation overrides applied, as accounts are added to ledgers and exports
+    pub fn relocated(&self) -> Result<Valid8Context> {
+        self.relocated_with_rewrites().map(|(relocated, _)| relocated)
+    }
+
+    /// The relocated context, with the number of references replaced in each account by its address before relocation
+    pub fn relocated_with_rewrites(&self) -> Result<(Valid8Context, BTreeMap<Pubkey, usize>)> {
+        // (from, to, rewrite_references, rewrite_programs)
+        let mut relocations: Vec<(Pubkey, Pubkey, bool, bool)> = vec![];
+        for over in self.overrides.iter().flatten() {
+            for edit_field in over.edit_fields.iter() {
+                if let EditField::Relocate { to, rewrite_references, rewrite_programs } = edit_field {
+                    relocations.push((over.pubkey, *to, *rewrite_references, *rewrite_programs));
+                }
+            }
+        }
+        if relocations.is_empty() {
+            return Ok((self.clone(), BTreeMap::new()))
+        }
+
+        // Upgradeable programs move with their program data account, at the address derived from the new program id
+        let mut program_data_addresses = vec![];
+        for (from, to, rewrite_references, rewrite_programs) in relocations.clone() {
+            if let Some(program) = self.programs.iter().find(|program| program.pubkey == from && program.executable && program.owner == bpf_loader_upgradeable::id()) {
+                let (new_program_data, _) = Pubkey::find_program_address(&[to.as_ref()], &bpf_loader_upgradeable::id());
+                relocations.push((program.get_program_executable_data_address()?, new_program_data, rewrite_references, rewrite_programs));
+                program_data_addresses.push((to, new_program_data));
+            }
+        }
+        let mut targets = HashSet::new();
+        for (from, to, _, _) in relocations.iter() {
+            if self.has_account(to) || self.has_program(to) || !targets.insert(*to) {
+                return Err(anyhow!("Can't relocate {} to {}, the address is already used", from, to))
+            }
+        }
+
+        let mut relocated = self.clone();
+        let mut rewrites = BTreeMap::new();
+        for account in relocated.programs.iter_mut().chain(relocated.accounts.iter_mut()) {
+            let pubkey = account.pubkey;
+            if let Some((_, to, _, _)) = relocations.iter().find(|(from, _, _, _)| *from == account.pubkey) {
+                account.pubkey = *to;
+            }
+            // Programs and program data are only rewritten when asked for, any 32 bytes of an ELF can match an address
+            let is_program = account.executable || account.owner == bpf_loader_upgradeable::id();
+            let mut replaced = 0;
+            for (from, to, _, _) in relocations.iter().filter(|(_, _, rewrite_references, rewrite_programs)| *rewrite_references && (*rewrite_programs || !is_program)) {
+                replaced += replace_pubkey(&mut account.data, from, to);
+                if account.owner == *from {
+                    account.owner = *to;
+                    replaced += 1;
+                }
+            }
+            if replaced > 0 {
+                rewrites.insert(pubkey, replaced);
+            }
+        }
+        for (program_id, program_data_address) in program_data_addresses {
+            if let Some(program) = relocated.programs.iter_mut().find(|program| program.pubkey == program_id) {
+                program.data = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address: program_data_address })?;
+            }
+        }
+        Ok((relocated, rewrites))
+    }
+
+    /// Builds a genesis config with all accounts and programs added
+    pub fn build_genesis(&self) -> Result<Valid8Genesis> {
+        let relocated = self.relocated()?;
         let mint_address = Keypair::new();
         let validator_identity = Keypair::new();
         let validator_vote_account = Keypair::new();
@@ -506,15 +1330,15 @@ impl Valid8Context {
 
         let mut accounts: HashMap<Pubkey, AccountSharedData> = HashMap::new();
 
-        let _ = self.programs
-            .into_iter()
+        let _ = relocated.programs
+            .iter()
             .map(|pa|{
                 let _ = accounts.insert(pa.pubkey, AccountSharedData::from(pa.to_account()?));
                 Ok(())
             }).collect::<Result<Vec<()>>>()?;
 
-        let _ = self.accounts
-            .into_iter()
+        let _ = relocated.accounts
+            .iter()
             .map(|a|{
                 let _ = accounts.insert(a.pubkey, AccountSharedData::from(a.to_account()?));
                 Ok(())
@@ -541,7 +1365,24 @@ impl Valid8Context {
 
         genesis_config.epoch_schedule = EpochSchedule::without_warmup();
 
-        let test_ledger_path = Path::new("test-ledger");
+        Ok(Valid8Genesis {
+            genesis_config,
+            validator_identity,
+            validator_vote_account,
+            validator_stake_account,
+            faucet_keypair,
+        })
+    }
+
+    /// Creates a solana-test-validator compatible ledger directory with all accounts and programs added at genesis
+    pub fn create_ledger(&self, test_ledger_path: &Path) -> Result<()> {
+        let Valid8Genesis {
+            genesis_config,
+            validator_identity,
+            validator_vote_account,
+            validator_stake_account,
+            faucet_keypair,
+        } = self.build_genesis()?;
 
         let _last_hash = create_new_ledger(
             test_ledger_path,
@@ -582,8 +1423,204 @@ impl Valid8Context {
             &faucet_keypair,
             test_ledger_path.join("faucet-keypair.json").to_str().unwrap(),
         ).map_err(|e| anyhow!(e.to_string()))?;
-        println!("ledger directory created: test-ledger");
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Replaces every occurrence of a pubkey in account data, returns the number of replacements
+fn replace_pubkey(data: &mut [u8], from: &Pubkey, to: &Pubkey) -> usize {
+    let mut i = 0;
+    let mut replaced = 0;
+    while i + 32 <= data.len() {
+        if data[i..i + 32] == from.to_bytes() {
+            data[i..i + 32].copy_from_slice(&to.to_bytes());
+            replaced += 1;
+            i += 32;
+        } else {
+            i += 1;
+        }
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_pubkey_counts_replacements() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // An aligned and an unaligned occurrence, separated by other bytes
+        let mut data = [from.to_bytes().as_slice(), &[7, 7, 7], from.to_bytes().as_slice(), &[9]].concat();
+
+        assert_eq!(replace_pubkey(&mut data, &from, &to), 2);
+        assert_eq!(data, [to.to_bytes().as_slice(), &[7, 7, 7], to.to_bytes().as_slice(), &[9]].concat());
+        assert_eq!(replace_pubkey(&mut data, &from, &to), 0);
+    }
+
+    #[test]
+    fn replace_pubkey_ignores_partial_matches() {
+        let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = from.to_bytes()[..31].to_vec();
+
+        assert_eq!(replace_pubkey(&mut data, &from, &to), 0);
+        assert_eq!(data, from.to_bytes()[..31]);
+    }
+
+    #[test]
+    fn replace_pubkey_doesnt_replace_inside_a_replacement() {
+        // The replaced bytes are skipped, so a match overlapping the end of a replacement isn't replaced as well
+        let from = Pubkey::new_from_array([1; 32]);
+        let to = Pubkey::new_from_array([2; 32]);
+        let mut data = vec![1; 40];
+
+        assert_eq!(replace_pubkey(&mut data, &from, &to), 1);
+        assert_eq!(data, [[2; 32].as_slice(), &[1; 8]].concat());
+    }
+
+    #[test]
+    fn rewrite_references_skips_programs() {
+        let program_id = Pubkey::new_unique();
+        let (fork_id, holder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = |pubkey: Pubkey, owner: Pubkey, executable: bool| AccountSchema {
+            pubkey,
+            network: Network::Imported,
+            lamports: 1,
+            data: program_id.to_bytes().to_vec(),
+            owner,
+            executable,
+            rent_epoch: 0,
+        };
+        let other_program = Pubkey::new_unique();
+        let mut ctx = Valid8Context {
+            programs: vec![account(other_program, solana_sdk::bpf_loader::id(), true)],
+            accounts: vec![account(holder, program_id, false)],
+            overrides: Some(vec![Override::new(program_id, EditField::Relocate { to: fork_id, rewrite_references: true, rewrite_programs: false })]),
+            ..Valid8Context::default()
+        };
+
+        let (relocated, rewrites) = ctx.relocated_with_rewrites().unwrap();
+        assert_eq!(relocated.accounts[0].data, fork_id.to_bytes());
+        assert_eq!(relocated.accounts[0].owner, fork_id);
+        assert_eq!(relocated.programs[0].data, program_id.to_bytes());
+        assert_eq!(rewrites, BTreeMap::from([(holder, 2)]));
+
+        ctx.overrides = Some(vec![Override::new(program_id, EditField::Relocate { to: fork_id, rewrite_references: true, rewrite_programs: true })]);
+        let (relocated, rewrites) = ctx.relocated_with_rewrites().unwrap();
+        assert_eq!(relocated.programs[0].data, fork_id.to_bytes());
+        assert_eq!(rewrites, BTreeMap::from([(holder, 2), (other_program, 1)]));
+    }
+
+    #[test]
+    fn apply_edit_is_idempotent() {
+        let ctx = Valid8Context::default();
+        let mut token_data = [0u8; TokenAccount::LEN];
+        TokenAccount { mint: Pubkey::new_unique(), owner: Pubkey::new_unique(), amount: 5, state: spl_token::state::AccountState::Initialized, ..TokenAccount::default() }
+            .pack_into_slice(&mut token_data);
+        let token_account = AccountSchema {
+            pubkey: Pubkey::new_unique(),
+            network: Network::Imported,
+            lamports: 1,
+            data: token_data.to_vec(),
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        // Program data accounts keep room for an authority in front of the ELF, even without one
+        let mut program_data_data = bincode::serialize(&UpgradeableLoaderState::ProgramData { slot: 7, upgrade_authority_address: None }).unwrap();
+        program_data_data.resize(UpgradeableLoaderState::size_of_programdata_metadata() + 4, 1);
+        let program_data = AccountSchema {
+            data: program_data_data,
+            owner: bpf_loader_upgradeable::id(),
+            ..token_account.clone()
+        };
+
+        let edits = [
+            (&token_account, EditField::Lamports(42)),
+            (&token_account, EditField::Owner(Pubkey::new_unique())),
+            (&token_account, EditField::TokenOwner(Pubkey::new_unique())),
+            (&token_account, EditField::TokenAmount(1_000)),
+            (&token_account, EditField::TokenDelegate(Pubkey::new_unique())),
+            (&token_account, EditField::TokenDelegatedAmount(10)),
+            (&program_data, EditField::UpgradeAuthority(Pubkey::new_unique())),
+        ];
+        for (account, edit_field) in edits {
+            let mut once = account.clone();
+            ctx.apply_edit(&mut once, &edit_field).unwrap();
+            assert_ne!(once, *account, "{:?} didn't change the account", edit_field);
+            let mut twice = once.clone();
+            ctx.apply_edit(&mut twice, &edit_field).unwrap();
+            assert_eq!(twice, once, "{:?} isn't idempotent", edit_field);
+        }
+    }
+
+    #[test]
+    fn edits_zero_copy_fields_in_place() {
+        // IDLs are read from the resources directory of the project, relative to the current directory
+        struct Resources(ProjectName);
+        impl Drop for Resources {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(self.0.to_resources());
+            }
+        }
+        let resources = Resources(ProjectName::from_str("valid8-zero-copy-edit-test").unwrap());
+        let program_id = Pubkey::new_unique();
+        let raw_idl = serde_json::json!({
+            "version": "0.1.0",
+            "name": "market",
+            "instructions": [],
+            "accounts": [{
+                "name": "Market",
+                "type": { "kind": "struct", "fields": [
+                    { "name": "bump", "type": "u8" },
+                    { "name": "price", "type": "i64" },
+                    { "name": "orders", "type": { "array": [{ "defined": "Order" }, 2] } },
+                ]},
+                "serialization": "bytemuck",
+                "repr": { "kind": "c" },
+            }],
+            "types": [{
+                "name": "Order",
+                "type": { "kind": "struct", "fields": [{ "name": "side", "type": "u8" }, { "name": "size", "type": "u64" }] },
+                "serialization": "bytemuck",
+                "repr": { "kind": "c" },
+            }],
+        });
+        create_dir_all(resources.0.to_resources()).unwrap();
+        helpers::save_idl(&resources.0, &program_id, &serde_json::to_vec(&raw_idl).unwrap()).unwrap();
+
+        let idl: Idl = serde_json::from_value(raw_idl.clone()).unwrap();
+        let values = serde_json::json!({ "bump": 254, "price": 10, "orders": [{ "side": 0, "size": 1 }, { "side": 1, "size": 2 }] });
+        let data = idl::pack_idl_account(&idl, &raw_idl, &idl.accounts[0], DiscriminatorStrategy::Anchor, &values).unwrap();
+        let mut account = AccountSchema {
+            pubkey: Pubkey::new_unique(),
+            network: Network::Imported,
+            lamports: 1,
+            data: data.clone(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let ctx = Valid8Context { project_name: resources.0.clone(), ..Valid8Context::default() };
+
+        for (field, value) in [("price", "-42"), ("orders[1].size", "18446744073709551615")] {
+            let edit_field = EditField::IdlField { program_id, account_type: "Market".into(), field: field.into(), value: value.into() };
+            ctx.apply_edit(&mut account, &edit_field).unwrap();
+        }
+
+        let fields = ctx.unpack_idl_data(&program_id, &account.data, "Market").unwrap().fields;
+        let shown: Vec<(String, String)> = fields.iter().map(|field| (field.name.clone(), field.value.as_ref().unwrap().to_string())).collect();
+        let expected = [("bump", "254"), ("price", "-42"), ("orders[0].side", "0"), ("orders[0].size", "1"), ("orders[1].side", "1"), ("orders[1].size", "18446744073709551615")];
+        assert_eq!(shown, expected.map(|(name, value)| (name.to_string(), value.to_string())));
+        // Only the bytes of the edited fields changed, after the discriminator: price at 8, orders[1].size at 40
+        assert_eq!(account.data.len(), data.len());
+        assert_eq!(account.data[16..24], (-42i64).to_le_bytes());
+        assert_eq!(account.data[48..56], u64::MAX.to_le_bytes());
+        assert_eq!(account.data[..16], data[..16]);
+        assert_eq!(account.data[24..48], data[24..48]);
+
+        let out_of_range = EditField::IdlField { program_id, account_type: "Market".into(), field: "bump".into(), value: "256".into() };
+        assert!(ctx.apply_edit(&mut account, &out_of_range).is_err());
+    }
+}
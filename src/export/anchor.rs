@@ -0,0 +1,49 @@
+use std::{fs::create_dir_all, path::Path};
+use anyhow::Result;
+
+use crate::{common::anchor_toml::{AnchorAccount, AnchorGenesis, AnchorTest, AnchorTestValidator, AnchorToml}, context::Valid8Context};
+
+use super::{export_accounts, export_programs, write_export_file, ExportReport};
+
+const ANCHOR_TOML_NAME: &str = "Anchor.test.toml";
+
+pub fn export_anchor_toml(ctx: &Valid8Context, out: &str) -> Result<ExportReport> {
+    let out_path = Path::new(out);
+    create_dir_all(out_path)?;
+
+    // Accounts and programs are exported with their edited data, instead of being cloned again by anchor test
+    let programs = export_programs(ctx)?;
+    let accounts = export_accounts(ctx, &programs);
+
+    let mut genesis = vec![];
+    for program in programs.iter() {
+        let file_name = format!("{}.so", program.program.pubkey);
+        write_export_file(out_path, &file_name, program.elf)?;
+        genesis.push(AnchorGenesis {
+            address: program.program.pubkey.to_string(),
+            program: out_path.join(file_name).display().to_string(),
+            upgradeable: Some(program.program_data.is_some()),
+        });
+    }
+
+    let mut anchor_accounts = vec![];
+    for account in accounts.iter() {
+        let file_name = format!("{}.json", account.pubkey);
+        write_export_file(out_path, &file_name, &account.export()?)?;
+        anchor_accounts.push(AnchorAccount {
+            address: account.pubkey.to_string(),
+            filename: out_path.join(file_name).display().to_string(),
+        });
+    }
+
+    let anchor_toml = AnchorToml {
+        test: Some(AnchorTest {
+            validator: Some(AnchorTestValidator { url: None, clone: vec![], account: anchor_accounts }),
+            genesis,
+        }),
+    };
+    let anchor_toml = format!("# Generated by valid8 export, copy these sections to your Anchor.toml\n{}", toml::to_string(&anchor_toml)?);
+    write_export_file(out_path, ANCHOR_TOML_NAME, anchor_toml.as_bytes())?;
+
+    Ok(ExportReport { accounts: accounts.len(), programs: programs.len(), entrypoint: Some(out_path.join(ANCHOR_TOML_NAME)) })
+}
@@ -0,0 +1,108 @@
+use std::{fs::create_dir_all, path::Path};
+use anyhow::Result;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::{common::AccountSchema, context::Valid8Context};
+
+use super::{export_accounts, export_programs, write_export_file, ExportReport};
+
+const MANIFEST_NAME: &str = "valid8.manifest.json";
+const LOADER_NAME: &str = "valid8.ts";
+
+const LOADER: &str = r#"// Generated by valid8 export
+import { readFileSync } from "fs";
+import { join } from "path";
+import { PublicKey } from "@solana/web3.js";
+
+type ManifestAccount = {
+  address: string;
+  info: { lamports: number; data: string; owner: string; executable: boolean; rentEpoch: number };
+};
+
+const manifest: {
+  programs: { programId: string; path: string; accounts: ManifestAccount[] }[];
+  accounts: ManifestAccount[];
+} = JSON.parse(readFileSync(join(__dirname, "valid8.manifest.json"), "utf8"));
+
+const toAddedAccount = ({ address, info }: ManifestAccount) => ({
+  address: new PublicKey(address),
+  info: {
+    lamports: info.lamports,
+    data: Buffer.from(info.data, "base64"),
+    owner: new PublicKey(info.owner),
+    executable: info.executable,
+    rentEpoch: info.rentEpoch,
+  },
+});
+
+/** Accounts for Bankrun's `start`/`startAnchor`, programs are included as their program and program data accounts */
+export const bankrunAccounts = [
+  ...manifest.programs.flatMap((program) => program.accounts),
+  ...manifest.accounts,
+].map(toAddedAccount);
+
+/** Programs as .so files, eg. for LiteSVM's `addProgramFromFile` */
+export const programs = manifest.programs.map(({ programId, path }) => ({
+  programId: new PublicKey(programId),
+  path: join(__dirname, path),
+}));
+
+/** Accounts without programs, eg. for LiteSVM's `setAccount` */
+export const accounts = manifest.accounts.map(toAddedAccount);
+
+/** Loads all programs and accounts into a LiteSVM instance */
+export function loadLiteSVM(svm: {
+  addProgramFromFile(programId: PublicKey, path: string): unknown;
+  setAccount(address: PublicKey, account: (typeof accounts)[number]["info"]): unknown;
+}) {
+  programs.forEach(({ programId, path }) => svm.addProgramFromFile(programId, path));
+  accounts.forEach(({ address, info }) => svm.setAccount(address, info));
+}
+"#;
+
+fn manifest_account(account: &AccountSchema) -> Value {
+    json!({
+        "address": account.pubkey.to_string(),
+        "info": {
+            "lamports": account.lamports,
+            "data": base64::engine::general_purpose::STANDARD.encode(&account.data),
+            "owner": account.owner.to_string(),
+            "executable": account.executable,
+            "rentEpoch": account.rent_epoch,
+        }
+    })
+}
+
+pub fn export_bankrun(ctx: &Valid8Context, out: &str) -> Result<ExportReport> {
+    let out_path = Path::new(out);
+    create_dir_all(out_path)?;
+
+    let programs = export_programs(ctx)?;
+    let accounts = export_accounts(ctx, &programs);
+
+    let mut manifest_programs = vec![];
+    for program in programs.iter() {
+        let path = format!("{}.so", program.program.pubkey);
+        write_export_file(out_path, &path, program.elf)?;
+        let program_accounts: Vec<Value> = [Some(program.program), program.program_data]
+            .into_iter()
+            .flatten()
+            .map(manifest_account)
+            .collect();
+        manifest_programs.push(json!({
+            "programId": program.program.pubkey.to_string(),
+            "path": path,
+            "accounts": program_accounts,
+        }));
+    }
+
+    let manifest = json!({
+        "programs": manifest_programs,
+        "accounts": accounts.iter().map(|account| manifest_account(account)).collect::<Vec<Value>>(),
+    });
+    write_export_file(out_path, MANIFEST_NAME, &serde_json::to_vec_pretty(&manifest)?)?;
+    write_export_file(out_path, LOADER_NAME, LOADER.as_bytes())?;
+
+    Ok(ExportReport { accounts: accounts.len(), programs: programs.len(), entrypoint: None })
+}
@@ -0,0 +1,95 @@
+use std::{fs::File, io::Write, path::{Path, PathBuf}};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use solana_sdk::{bpf_loader_upgradeable::{self, UpgradeableLoaderState}, pubkey::Pubkey};
+
+use crate::{common::AccountSchema, context::Valid8Context};
+
+pub mod validator;
+pub use validator::*;
+
+pub mod program_test;
+pub use program_test::*;
+
+pub mod bankrun;
+pub use bankrun::*;
+
+pub mod anchor;
+pub use anchor::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One RPC style account json per account, plus a solana-test-validator command line
+    ValidatorJson,
+    /// A Rust module adding all accounts to a solana-program-test ProgramTest
+    ProgramTest,
+    /// A json manifest and TS loader for Bankrun and LiteSVM
+    Bankrun,
+    /// Account json files and .so programs, with the Anchor.toml sections loading them
+    AnchorToml,
+}
+
+/// What an export wrote to the out directory
+#[derive(Debug, Clone)]
+pub struct ExportReport {
+    pub accounts: usize,
+    pub programs: usize,
+    /// The file to start from, the validator script or the Anchor.toml sections
+    pub entrypoint: Option<PathBuf>,
+}
+
+/// Writes all accounts and programs of a context to the out directory in the given format
+pub fn export(ctx: &Valid8Context, format: ExportFormat, out: &str) -> Result<ExportReport> {
+    let ctx = &ctx.relocated()?;
+    match format {
+        ExportFormat::ValidatorJson => export_validator_json(ctx, out),
+        ExportFormat::ProgramTest => export_program_test(ctx, out),
+        ExportFormat::Bankrun => export_bankrun(ctx, out),
+        ExportFormat::AnchorToml => export_anchor_toml(ctx, out),
+    }
+}
+
+pub struct ExportProgram<'a> {
+    pub program: &'a AccountSchema,
+    // Only upgradeable programs have a program data account
+    pub program_data: Option<&'a AccountSchema>,
+    pub upgrade_authority: Option<Pubkey>,
+    pub elf: &'a [u8],
+}
+
+pub fn export_programs(ctx: &Valid8Context) -> Result<Vec<ExportProgram<'_>>> {
+    ctx.programs.iter().map(|program| {
+        if program.owner != bpf_loader_upgradeable::id() {
+            return Ok(ExportProgram { program, program_data: None, upgrade_authority: None, elf: &program.data })
+        }
+        let program_data_address = program.get_program_executable_data_address()?;
+        let program_data = ctx.accounts
+            .iter()
+            .find(|acc| acc.pubkey == program_data_address)
+            .ok_or(anyhow!("Program data {} for {} not found in context", program_data_address, program.pubkey))?;
+        let upgrade_authority = match bincode::deserialize(&program_data.data)? {
+            UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. } => upgrade_authority_address,
+            _ => return Err(anyhow!("Invalid program data account {}", program_data_address)),
+        };
+        Ok(ExportProgram {
+            program,
+            program_data: Some(program_data),
+            upgrade_authority,
+            elf: &program_data.data[UpgradeableLoaderState::size_of_programdata_metadata()..],
+        })
+    }).collect()
+}
+
+pub fn export_accounts<'a>(ctx: &'a Valid8Context, programs: &[ExportProgram]) -> Vec<&'a AccountSchema> {
+    // Program data accounts are exported with their program
+    ctx.accounts
+        .iter()
+        .filter(|acc| !programs.iter().any(|program| program.program_data.is_some_and(|program_data| program_data.pubkey == acc.pubkey)))
+        .collect()
+}
+
+pub fn write_export_file(out_path: &Path, file_name: &str, data: &[u8]) -> Result<()> {
+    File::create(out_path.join(file_name))
+        .and_then(|mut file| file.write_all(data))
+        .map_err(|e| anyhow!("Failed to write {}: {}", file_name, e))
+}
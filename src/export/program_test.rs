@@ -0,0 +1,45 @@
+use std::{fs::create_dir_all, path::Path};
+use anyhow::Result;
+
+use crate::{common::AccountSchema, context::Valid8Context};
+
+use super::{export_accounts, export_programs, write_export_file, ExportReport};
+
+const MODULE_NAME: &str = "mod.rs";
+
+fn add_account_snippet(account: &AccountSchema) -> String {
+    format!(
+        "    program_test.add_account(\n        pubkey!(\"{}\"),\n        Account {{\n            lamports: {},\n            data: include_bytes!(\"{}.bin\").to_vec(),\n            owner: pubkey!(\"{}\"),\n            executable: {},\n            rent_epoch: {},\n        }},\n    );\n",
+        account.pubkey, account.lamports, account.pubkey, account.owner, account.executable, account.rent_epoch
+    )
+}
+
+pub fn export_program_test(ctx: &Valid8Context, out: &str) -> Result<ExportReport> {
+    let out_path = Path::new(out);
+    create_dir_all(out_path)?;
+
+    // Programs are added as their program and program data accounts, which ProgramTest loads like any deployed program
+    let programs = export_programs(ctx)?;
+    let accounts = export_accounts(ctx, &programs);
+    let program_accounts: Vec<&AccountSchema> = programs
+        .iter()
+        .flat_map(|program| [Some(program.program), program.program_data])
+        .flatten()
+        .collect();
+
+    let mut module = String::from("// Generated by valid8 export, include it with `mod <dir>;`\nuse solana_program_test::ProgramTest;\nuse solana_sdk::{account::Account, pubkey};\n\n");
+    module.push_str("/// Adds all programs cloned with valid8 to a ProgramTest\n#[allow(unused_variables)]\npub fn add_programs(program_test: &mut ProgramTest) {\n");
+    for account in program_accounts.iter() {
+        write_export_file(out_path, &format!("{}.bin", account.pubkey), &account.data)?;
+        module.push_str(&add_account_snippet(account));
+    }
+    module.push_str("}\n\n/// Adds all accounts cloned or created with valid8 to a ProgramTest\n#[allow(unused_variables)]\npub fn add_accounts(program_test: &mut ProgramTest) {\n");
+    for account in accounts.iter() {
+        write_export_file(out_path, &format!("{}.bin", account.pubkey), &account.data)?;
+        module.push_str(&add_account_snippet(account));
+    }
+    module.push_str("}\n\n/// Adds all programs and accounts to a ProgramTest\npub fn add_all(program_test: &mut ProgramTest) {\n    add_programs(program_test);\n    add_accounts(program_test);\n}\n");
+
+    write_export_file(out_path, MODULE_NAME, module.as_bytes())?;
+    Ok(ExportReport { accounts: accounts.len(), programs: programs.len(), entrypoint: None })
+}
@@ -0,0 +1,47 @@
+use std::{fs::{self, create_dir_all}, path::Path};
+use anyhow::Result;
+
+use crate::context::Valid8Context;
+
+use super::{export_accounts, export_programs, write_export_file, ExportReport};
+
+const SCRIPT_NAME: &str = "test-validator.sh";
+
+pub fn export_validator_json(ctx: &Valid8Context, out: &str) -> Result<ExportReport> {
+    let out_path = Path::new(out);
+    create_dir_all(out_path)?;
+
+    // Programs are passed as .so files, their program data accounts are skipped
+    let programs = export_programs(ctx)?;
+    let accounts = export_accounts(ctx, &programs);
+    for program in programs.iter() {
+        write_export_file(out_path, &format!("{}.so", program.program.pubkey), program.elf)?;
+    }
+    for account in accounts.iter() {
+        write_export_file(out_path, &format!("{}.json", account.pubkey), &account.export()?)?;
+    }
+
+    // Generate the full command line, paths are relative to the script
+    let mut script = String::from("#!/usr/bin/env bash\n# Generated by valid8 export\nDIR=\"$(cd \"$(dirname \"$0\")\" && pwd)\"\n\nsolana-test-validator \\\n    --reset \\\n");
+    programs.iter().for_each(|program| match program.program_data {
+        Some(_) => {
+            let upgrade_authority = program.upgrade_authority.map_or("none".to_string(), |authority| authority.to_string());
+            script.push_str(&format!("    --upgradeable-program {0} \"$DIR/{0}.so\" {1} \\\n", program.program.pubkey, upgrade_authority))
+        },
+        None => script.push_str(&format!("    --bpf-program {0} \"$DIR/{0}.so\" \\\n", program.program.pubkey)),
+    });
+    accounts.iter().for_each(|account| {
+        script.push_str(&format!("    --account {0} \"$DIR/{0}.json\" \\\n", account.pubkey))
+    });
+    script.push_str("    \"$@\"\n");
+
+    write_export_file(out_path, SCRIPT_NAME, script.as_bytes())?;
+    let script_path = out_path.join(SCRIPT_NAME);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(ExportReport { accounts: accounts.len(), programs: programs.len(), entrypoint: Some(script_path) })
+}
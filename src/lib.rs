@@ -0,0 +1,21 @@
+//! valid8 clones Solana accounts and programs into a local config, edits them with overrides,
+//! and builds genesis ledgers or test fixtures from them.
+//!
+//! The library has no interactive prompts, everything the `valid8` CLI does with user input is
+//! available here with explicit values:
+//!
+//! - [`Valid8Context::load`] / [`Valid8Context::install`] open a `valid8.json` project
+//! - [`Valid8Context::apply_overrides`] replays the recorded edits
+//! - [`Valid8Context::build_genesis`] / [`Valid8Context::create_ledger`] build the genesis ledger
+//! - [`export::export`] writes fixtures for solana-test-validator, program-test and Bankrun
+
+pub mod common;
+pub mod config;
+pub mod context;
+pub mod export;
+pub mod serialization;
+
+pub use common::{project_name::ProjectName, AccountSchema, Network};
+pub use config::{ConfigEntry, ConfigJson, EntryMetadata};
+pub use context::{CreatedAccount, EditField, Override, OverrideState, RebaseReport, UnpackedIdlAccount, Valid8Context, Valid8Genesis};
+pub use export::ExportFormat;
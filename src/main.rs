@@ -1,14 +1,12 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use context::Valid8Context;
+use solana_sdk::pubkey::Pubkey;
+use valid8::{common::compose::MergePolicy, export::ExportFormat, Network, Valid8Context};
 
 mod account;
 mod commands;
-mod context;
 mod program;
-mod common;
-mod serialization;
-mod config;
+mod prompt;
 
 // const APP_NAME: &str = "Valid8";
 
@@ -17,6 +15,9 @@ mod config;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Only use accounts saved in .valid8, without any RPC calls
+    #[arg(long, global = true)]
+    offline: bool,
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -28,13 +29,157 @@ enum Commands {
     /// Generate a custom ledger with accounts and programs added at genesis
     Ledger {overwrite_if_exists: Option<String>},
     /// Compose multiple valid8 configs
-    Compose,
+    Compose {
+        /// How conflicting definitions are resolved, overrides `compose_policy` of the config
+        #[arg(long, value_enum)]
+        policy: Option<MergePolicy>,
+        /// Fetch the latest version of remote includes instead of the locked one
+        #[arg(long)]
+        update: bool,
+        /// Only merge accounts and programs of included configs with this tag, can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Manage program IDLs
+    Idl {
+        #[command(subcommand)]
+        command: commands::IdlCommand,
+    },
+    /// Derive a PDA from the seeds in a program IDL
+    Pda {
+        /// Program id or label
+        program_id: String,
+        account_name: String,
+        /// Seed value as <path>=<value>, for arg and account seeds
+        #[arg(long = "seed", value_parser = commands::parse_seed)]
+        seeds: Vec<(String, String)>,
+        /// Clone the derived account from this network
+        #[arg(long)]
+        clone: Option<Network>,
+    },
+    /// Create a new account from an IDL account type and json values
+    CreateAccount(commands::CreateAccountArgs),
+    /// Export accounts and programs for use outside of valid8
+    Export {
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Output directory
+        #[arg(long)]
+        out: String,
+        /// Only export accounts and programs with this tag, can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Initialize a valid8 config, optionally from the test validator sections of an Anchor.toml
+    Init {
+        #[arg(long = "from-anchor")]
+        from_anchor: Option<String>,
+    },
+    /// Pack the config, accounts, programs and IDLs into a single bundle file
+    Pack {
+        #[arg(short, long, default_value = "fixtures.v8")]
+        out: String,
+    },
+    /// Restore a bundle created with pack
+    Unpack {
+        path: String,
+        /// Overwrite an existing config
+        #[arg(long)]
+        force: bool,
+    },
+    /// Manage the shared account data cache
+    Cache {
+        #[command(subcommand)]
+        command: commands::CacheCommand,
+    },
+    /// Import accounts from `solana account --output json` files or directories of them
+    Import {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Fetch the current state of cloned accounts, all without pubkeys, and update the lockfile
+    Update {
+        pubkeys: Vec<String>,
+    },
+    /// Compare accounts and programs, all without pubkeys, with their live state on the network
+    Diff {
+        pubkeys: Vec<String>,
+        /// Compare with this network instead of the one each account was cloned from
+        #[arg(long)]
+        network: Option<Network>,
+        /// Print the differences as json
+        #[arg(long)]
+        json: bool,
+    },
+    /// List and revert the edits of accounts
+    Overrides {
+        #[command(subcommand)]
+        command: commands::OverridesCommand,
+    },
+    /// Move an account or program to a new address in ledgers and exports
+    Relocate {
+        /// Pubkey or label
+        pubkey: String,
+        to: Pubkey,
+        /// Replace the old address in the data and owner of all accounts, eg. for a fork under a new program id
+        #[arg(long)]
+        rewrite_references: bool,
+        /// Also replace it in programs and program data, they're skipped as any bytes of an ELF can match an address
+        #[arg(long, requires = "rewrite_references")]
+        rewrite_programs: bool,
+    },
+    /// Move a program and its accounts and PDAs to a new program id
+    RebaseProgram {
+        /// Program id or label
+        from: String,
+        to: Pubkey,
+    },
+    /// Set the label of an account or program, usable instead of its pubkey, or remove it without a label
+    Label {
+        /// Pubkey or current label
+        pubkey: String,
+        label: Option<String>,
+    },
+    /// Add tags to an account or program
+    Tag {
+        /// Pubkey or label
+        pubkey: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Remove the tags instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// List accounts and programs with their labels and tags
+    List {
+        /// Only list accounts and programs with this tag, can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Check the project config
+    Config {
+        #[command(subcommand)]
+        command: commands::ConfigCommand,
+    },
+    /// Manage how accounts are stored on disc
+    Storage {
+        #[command(subcommand)]
+        command: commands::StorageCommand,
+    },
 }
 
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let ctx = Valid8Context::init()?;
+
+    // Commands that don't use a project config run without creating one
+    match &cli.command {
+        Some(Commands::Unpack{path, force}) => return commands::unpack(path, *force),
+        Some(Commands::Cache{command}) => return commands::cache(command),
+        Some(Commands::Config{command}) => return commands::config(command, cli.offline),
+        _ => {}
+    }
+    let ctx = Valid8Context { offline: cli.offline, ..prompt::init_context(cli.offline)? };
 
     router(&cli, ctx)
 
@@ -47,7 +192,26 @@ fn router(cli: &Cli, mut ctx: Valid8Context) -> Result<()> {
             Commands::Run => commands::run(ctx)?,
             Commands::Edit => commands::edit(&mut ctx)?,
             Commands::Ledger{overwrite_if_exists} => commands::ledger(ctx, overwrite_if_exists)?,
-            Commands::Compose => commands::compose(ctx)?,
+            Commands::Compose{policy, update, tags} => commands::compose(ctx, *policy, *update, tags)?,
+            Commands::Idl{command} => commands::idl(&mut ctx, command)?,
+            Commands::Pda{program_id, account_name, seeds, clone} => commands::pda(&mut ctx, program_id, account_name, seeds, clone)?,
+            Commands::CreateAccount(args) => commands::create_account(&mut ctx, args)?,
+            Commands::Export{format, out, tags} => commands::export(&ctx, *format, out, tags)?,
+            Commands::Init{from_anchor} => commands::init(&mut ctx, from_anchor)?,
+            Commands::Pack{out} => commands::pack(&ctx, out)?,
+            Commands::Unpack{path, force} => commands::unpack(path, *force)?,
+            Commands::Cache{command} => commands::cache(command)?,
+            Commands::Import{paths} => commands::import(&mut ctx, paths)?,
+            Commands::Config{command} => commands::config(command, cli.offline)?,
+            Commands::Update{pubkeys} => commands::update(&mut ctx, pubkeys)?,
+            Commands::Diff{pubkeys, network, json} => commands::diff(&ctx, pubkeys, network, *json)?,
+            Commands::Overrides{command} => commands::overrides(&mut ctx, command)?,
+            Commands::Relocate{pubkey, to, rewrite_references, rewrite_programs} => commands::relocate(&mut ctx, pubkey, to, *rewrite_references, *rewrite_programs)?,
+            Commands::RebaseProgram{from, to} => commands::rebase_program(&mut ctx, from, to)?,
+            Commands::Label{pubkey, label} => commands::label(&mut ctx, pubkey, label)?,
+            Commands::Tag{pubkey, tags, remove} => commands::tag(&mut ctx, pubkey, tags, *remove)?,
+            Commands::List{tags} => commands::list(&ctx, tags)?,
+            Commands::Storage{command} => commands::storage(&mut ctx, command)?,
         }
     } else {
         commands::run(ctx)?
@@ -3,13 +3,12 @@ use dialoguer::Input;
 use solana_sdk::pubkey::Pubkey;
 
 use std::str::FromStr;
-use crate::{
-    common::network, 
-    context::Valid8Context
-};
+use valid8::Valid8Context;
+
+use crate::prompt;
 
 pub fn clone(ctx: &mut Valid8Context) -> Result<()> {
-    let network = network::get(ctx)?;
+    let network = prompt::network(ctx)?;
     let mut program_id: Option<Pubkey> = None;
     while program_id.is_none() {
         let program_id_string: String = Input::new()
@@ -21,6 +20,9 @@ pub fn clone(ctx: &mut Valid8Context) -> Result<()> {
             Err(_) => println!("Invalid address: {}. Please enter a valid base58-encoeded Solana address.", &program_id_string)
         }
     }
-    let pubkey = program_id.ok_or(anyhow!("Public key not defined"))?;   
+    let pubkey = program_id.ok_or(anyhow!("Public key not defined"))?;
+    if ctx.has_program(&pubkey) {
+        println!("{} already added", pubkey);
+    }
     ctx.add_program(&network, &pubkey)
 }
\ No newline at end of file
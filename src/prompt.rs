@@ -0,0 +1,87 @@
+use std::path::Path;
+use anyhow::{anyhow, Error, Result};
+use dialoguer::{Input, Select};
+use solana_sdk::pubkey::Pubkey;
+
+use valid8::{ProjectName, Network, Valid8Context};
+
+pub fn init_context(offline: bool) -> Result<Valid8Context> {
+    let project_name = ProjectName::default();
+
+    // Offline contexts are never installed, so there is nothing to ask
+    if offline && Path::new(&project_name.to_config()).exists() {
+        return Valid8Context::load_offline(&project_name)
+    }
+
+    // An invalid config is reported instead of replaced with a new one
+    if !Path::new(&project_name.to_config()).exists() {
+        return Valid8Context::try_init_config(&project_name)
+    }
+
+    let (config, installed) = Valid8Context::try_open_config(&project_name)?;
+    if !installed {
+        let choice: String = Input::new().with_prompt("Install Accounts to local?[y/n]").interact_text()?;
+
+        match choice.as_str() {
+            "y" => Ok(config.to_context(offline)?),
+            _ => Err(anyhow!("Accounts not installed")),
+        }
+
+    } else {
+        eprintln!("{} config found, accounts installed: {}", project_name.to_config(), installed);
+        Ok(config.into())
+    }
+}
+
+pub fn network(ctx: &Valid8Context) -> Result<Network> {
+    let mut items: Vec<String> = vec!["mainnet".into(), "devnet".into(), "custom".into(), "exit".into()];
+    for network in ctx.networks.iter() {
+        if !items.contains(&network.to_string()) {
+            items.push(network.to_string())
+        }
+    }
+    let selection = Select::new()
+        .with_prompt("Select a network")
+        .items(&items)
+        .interact()?;
+    
+    match selection {
+        0 => Ok(Network::Mainnet),
+        1 => Ok(Network::Devnet),
+        2 => custom_network(),
+        3 => Err(Error::msg("Exit")),
+        _ => if items.len() > selection {
+            Ok(Network::Custom(items[selection].clone()))
+        } else {
+            Err(Error::msg("Invalid network selection"))
+        }
+    }   
+}
+
+pub fn custom_network() -> Result<Network> {
+    let address = Input::new()
+        .with_prompt("Network address")
+        .with_initial_text("https://")
+        .interact()?;
+    Ok(Network::Custom(address))
+}
+
+/// A pubkey with its label and tags, as shown in menus
+pub fn menu_item(ctx: &Valid8Context, pubkey: &Pubkey) -> String {
+    match ctx.tags(pubkey) {
+        [] => ctx.display_name(pubkey),
+        tags => format!("{} [{}]", ctx.display_name(pubkey), tags.join(", ")),
+    }
+}
+
+pub fn select_pubkey(ctx: &Valid8Context, prompt: &str, pubkeys: &[Pubkey]) -> Result<Pubkey> {
+    if pubkeys.is_empty() {
+        return Err(anyhow!("Nothing to select, clone an account or program first"))
+    }
+    let items = pubkeys.iter().map(|pubkey| menu_item(ctx, pubkey)).collect::<Vec<String>>();
+    let selection = Select::new()
+        .with_prompt(prompt)
+        .items(&items)
+        .interact()?;
+    Ok(pubkeys[selection])
+}